@@ -1,35 +1,127 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+// the most buckets a summary histogram may have, as each one is allocated up front
+const MAX_BUCKETS: u64 = 100_000;
+
 pub(crate) struct Day01 {
-    vals: Vec<Vec<i32>>,
+    totals: Vec<u64>,
+    // how many items each elf carries
+    item_counts: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ElfReport {
+    pub(crate) index: usize,
+    pub(crate) total: u64,
+    pub(crate) items: usize,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Summary {
+    pub(crate) mean: f64,
+    pub(crate) median: f64,
+    /// The start of each bucket of calorie totals along with how many elves fall in it.
+    pub(crate) histogram: Vec<(u64, usize)>,
 }
 
 impl Day01 {
     pub(crate) fn new() -> Self {
-        let vals = super::super::utils::read_day_grouped_ints(1);
-        return Self { vals };
+        // a single pass over the stream, keeping only each elf's total and item count
+        let mut item_counts = vec![];
+        let groups = super::super::utils::stream_day_grouped_ints::<u64>(1)
+            .inspect(|group| item_counts.push(group.len()));
+        let totals = super::super::utils::checked_group_sums(groups)
+            .unwrap_or_else(|error| panic!("{}", error));
+        return Self {
+            totals,
+            item_counts,
+        };
+    }
+
+    /// The `k` elves carrying the most calories, most first. Ties go to the earlier elf.
+    pub(crate) fn top_elves(&self, k: usize) -> Vec<ElfReport> {
+        // min-heap of the best k seen so far, so the weakest is the one evicted
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        for (index, total) in self.totals.iter().copied().enumerate() {
+            heap.push(Reverse((total, Reverse(index))));
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+
+        return heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((total, Reverse(index)))| ElfReport {
+                index,
+                total,
+                items: self.item_counts[index],
+            })
+            .collect();
+    }
+
+    /// Statistics over every elf's total, with the histogram split into `bucket_width` buckets.
+    #[allow(dead_code)]
+    pub(crate) fn summary(&self, bucket_width: u64) -> Result<Summary, String> {
+        let mut totals = self.totals.clone();
+        if totals.is_empty() {
+            return Err("There are no elves to summarise".to_string());
+        }
+        if bucket_width == 0 {
+            return Err("Histogram buckets need a width of at least 1".to_string());
+        }
+
+        let len = totals.len();
+        let mean = totals.iter().map(|total| *total as f64).sum::<f64>() / len as f64;
+
+        let (lower, upper, _) = totals.select_nth_unstable(len / 2);
+        let upper = *upper as f64;
+        let median = if len.is_multiple_of(2) {
+            (*lower.iter().max().unwrap() as f64 + upper) / 2.0
+        } else {
+            upper
+        };
+
+        let min_bucket = totals.iter().min().unwrap().div_euclid(bucket_width);
+        let max_bucket = totals.iter().max().unwrap().div_euclid(bucket_width);
+        if max_bucket - min_bucket >= MAX_BUCKETS {
+            return Err(format!(
+                "A bucket width of {} needs {} buckets, more than the limit of {}",
+                bucket_width,
+                max_bucket - min_bucket + 1,
+                MAX_BUCKETS
+            ));
+        }
+        let mut histogram: Vec<(u64, usize)> = (min_bucket..=max_bucket)
+            .map(|bucket| (bucket * bucket_width, 0))
+            .collect();
+        for total in totals.iter() {
+            histogram[(total.div_euclid(bucket_width) - min_bucket) as usize].1 += 1;
+        }
+
+        return Ok(Summary {
+            mean,
+            median,
+            histogram,
+        });
     }
 }
 
 impl super::Day for Day01 {
     fn part_1(&mut self) -> String {
-        let max_sum = self
-            .vals
-            .iter()
-            .map(|group| group.into_iter().fold(0, |acc, val| acc + val))
-            .max();
+        let max_sum = self.top_elves(1).first().map(|elf| elf.total);
 
         return max_sum.unwrap_or_default().to_string();
     }
 
     fn part_2(&mut self) -> String {
-        let mut sums = self
-            .vals
+        return self
+            .top_elves(3)
             .iter()
-            .map(|group| group.into_iter().fold(0, |acc, val| acc + val))
-            .collect::<Vec<_>>();
-
-        sums.sort();
-        sums.reverse();
-        return (sums[0] + sums[1] + sums[2]).to_string();
+            .try_fold(0_u64, |acc, elf| acc.checked_add(elf.total))
+            .expect("Total of the top 3 elves overflowed")
+            .to_string();
     }
 }
 
@@ -49,4 +141,55 @@ mod test {
         let mut day = Day01::new();
         assert_eq!(day.part_2(), "45000");
     }
+
+    #[test]
+    fn top_elves() {
+        let day = Day01::new();
+        let elves: Vec<(usize, u64, usize)> = day
+            .top_elves(3)
+            .iter()
+            .map(|elf| (elf.index, elf.total, elf.items))
+            .collect();
+        assert_eq!(elves, vec![(3, 24000, 3), (2, 11000, 2), (4, 10000, 1)]);
+        assert_eq!(day.top_elves(10).len(), 5);
+    }
+
+    #[test]
+    fn summary() {
+        let day = Day01::new();
+        let summary = day.summary(5000).unwrap();
+        assert_eq!(summary.mean, 11000.0);
+        assert_eq!(summary.median, 10000.0);
+        assert_eq!(
+            summary.histogram,
+            vec![(0, 1), (5000, 1), (10000, 2), (15000, 0), (20000, 1)]
+        );
+        assert!(day.summary(0).is_err());
+        let wide = Day01 {
+            totals: vec![0, 1_000_000],
+            item_counts: vec![1, 1],
+        };
+        assert_eq!(
+            wide.summary(1).unwrap_err(),
+            "A bucket width of 1 needs 1000001 buckets, more than the limit of 100000"
+        );
+        assert_eq!(wide.summary(100).unwrap().histogram.len(), 10001);
+    }
+
+    #[test]
+    fn overflow() {
+        let groups: Vec<Vec<u8>> = vec![vec![200, 50], vec![200, 100]];
+        let error = crate::utils::checked_group_sums(groups).unwrap_err();
+        assert_eq!(error.to_string(), "Total of group 2 overflowed");
+    }
+
+    #[test]
+    #[should_panic(expected = "Total of the top 3 elves overflowed")]
+    fn top_three_overflow() {
+        let mut day = Day01 {
+            totals: vec![u64::MAX, 1],
+            item_counts: vec![1, 1],
+        };
+        day.part_2();
+    }
 }
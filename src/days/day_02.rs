@@ -1,78 +1,246 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Outcome {
+    Loss,
+    Draw,
+    Win,
+}
+
+/// A cyclic game where each move beats the `(n - 1) / 2` moves before it, wrapping around.
+pub(crate) struct Game {
+    move_scores: Vec<i32>,
+    outcome_scores: [i32; 3],
+}
+
+impl Game {
+    /// `move_scores[i]` is the score for playing move `i`, and `outcome_scores` is indexed by
+    /// [`Outcome`].
+    pub(crate) fn new(move_scores: Vec<i32>, outcome_scores: [i32; 3]) -> Result<Self, String> {
+        // with a single move every round is a draw, so nothing can win or lose
+        if move_scores.len() < 3 || move_scores.len().is_multiple_of(2) {
+            return Err(format!(
+                "Cyclic games need an odd number of moves, at least 3, found {}",
+                move_scores.len()
+            ));
+        }
+        return Ok(Self {
+            move_scores,
+            outcome_scores,
+        });
+    }
+
+    /// Rock, Paper, Scissors.
+    pub(crate) fn rock_paper_scissors() -> Self {
+        return Self::new(vec![1, 2, 3], [0, 3, 6]).unwrap();
+    }
+
+    /// Rock, Spock, Paper, Lizard, Scissors.
+    #[allow(dead_code)]
+    pub(crate) fn rock_paper_scissors_lizard_spock() -> Self {
+        return Self::new(vec![1, 2, 3, 4, 5], [0, 3, 6]).unwrap();
+    }
+
+    pub(crate) fn moves(&self) -> usize {
+        return self.move_scores.len();
+    }
+
+    pub(crate) fn outcome(&self, ours: usize, theirs: usize) -> Outcome {
+        let distance = (ours + self.moves() - theirs) % self.moves();
+        if distance == 0 {
+            return Outcome::Draw;
+        } else if distance <= self.moves() / 2 {
+            return Outcome::Win;
+        } else {
+            return Outcome::Loss;
+        }
+    }
+
+    /// The best scoring move that gives `outcome` against `theirs`.
+    pub(crate) fn response(&self, theirs: usize, outcome: Outcome) -> usize {
+        return (0..self.moves())
+            .filter(|ours| self.outcome(*ours, theirs) == outcome)
+            .max_by_key(|ours| self.move_scores[*ours])
+            .unwrap();
+    }
+
+    pub(crate) fn score(&self, ours: usize, theirs: usize) -> i32 {
+        return self.move_scores[ours] + self.outcome_scores[self.outcome(ours, theirs) as usize];
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Round {
+    pub(crate) theirs: usize,
+    pub(crate) ours: usize,
+    pub(crate) outcome: Outcome,
+    pub(crate) score: i32,
+}
+
+/// How many rounds were lost, drawn and won, indexed by [`Outcome`].
+#[allow(dead_code)]
+pub(crate) fn outcome_counts(rounds: &[Round]) -> [usize; 3] {
+    let mut counts = [0; 3];
+    for round in rounds {
+        counts[round.outcome as usize] += 1;
+    }
+    return counts;
+}
+
+// every ordered selection of `len` distinct values from 0..n
+#[allow(dead_code)]
+fn arrangements(n: usize, len: usize) -> Vec<Vec<usize>> {
+    if len == 0 {
+        return vec![vec![]];
+    }
+    let mut result = vec![];
+    for prefix in arrangements(n, len - 1) {
+        for next in (0..n).filter(|next| !prefix.contains(next)) {
+            let mut arrangement = prefix.clone();
+            arrangement.push(next);
+            result.push(arrangement);
+        }
+    }
+    return result;
+}
+
 pub(crate) struct Day02 {
-    vals: Vec<(i32, i32)>,
+    game: Game,
+    // their move, and the index of our letter in the guide
+    vals: Vec<(usize, usize)>,
+    #[allow(dead_code)]
+    our_letters: usize,
 }
 
 impl Day02 {
     pub(crate) fn new() -> Self {
-        let vals = crate::utils::read_day_lines(2);
-        let parts = vals
-            .iter()
-            .map(|str| {
-                let their_play: i32;
-                match str.chars().nth(0).unwrap_or('?') {
-                    'A' => their_play = 0,
-                    'B' => their_play = 1,
-                    'C' => their_play = 2,
-                    _ => panic!(
-                        "Unrecognized opponent play: {}",
-                        str.chars().nth(0).unwrap_or('_')
-                    ),
-                }
-                let our_play: i32;
-                match str.chars().nth(2).unwrap_or('?') {
-                    'X' => our_play = 0,
-                    'Y' => our_play = 1,
-                    'Z' => our_play = 2,
-                    _ => panic!(
-                        "Unrecognized player play: {}",
-                        str.chars().nth(0).unwrap_or('_')
-                    ),
-                }
-                return (their_play, our_play);
+        return Self::with_rules(Game::rock_paper_scissors(), "ABC", "XYZ")
+            .unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// Reads the guide with `their_letters[i]` meaning they play move `i`, and our letters
+    /// left for the strategy to interpret. We can't have more letters than there are moves, as
+    /// each one may be read as a different move.
+    pub(crate) fn with_rules(
+        game: Game,
+        their_letters: &str,
+        our_letters: &str,
+    ) -> Result<Self, String> {
+        if their_letters.chars().count() != game.moves() {
+            return Err(format!(
+                "Expected a letter for each of their {} moves, found {:?}",
+                game.moves(),
+                their_letters
+            ));
+        }
+        if our_letters.chars().count() > game.moves() {
+            return Err(format!(
+                "Expected at most {} of our letters, one per move, found {:?}",
+                game.moves(),
+                our_letters
+            ));
+        }
+
+        let vals = crate::utils::stream_day_lines(2)
+            .enumerate()
+            .map(|(i, str)| {
+                let mut letters = str.split_ascii_whitespace();
+                let their_letter = letters.next().and_then(|str| str.chars().next());
+                let their_play = their_letters
+                    .chars()
+                    .position(|letter| their_letter == Some(letter))
+                    .ok_or(format!(
+                        "line {}: Unrecognized opponent play: {}",
+                        i + 1,
+                        str
+                    ))?;
+                let our_letter = letters.next().and_then(|str| str.chars().next());
+                let our_play = our_letters
+                    .chars()
+                    .position(|letter| our_letter == Some(letter))
+                    .ok_or(format!("line {}: Unrecognized player play: {}", i + 1, str))?;
+                return Ok((their_play, our_play));
             })
-            .collect();
-        return Self { vals: parts };
+            .collect::<Result<_, String>>()?;
+        return Ok(Self {
+            game,
+            vals,
+            our_letters: our_letters.chars().count(),
+        });
     }
-}
 
-impl super::Day for Day02 {
-    fn part_1(&mut self) -> String {
-        let score = self
+    /// Each round played if our letter `i` means playing `moves[i]`.
+    pub(crate) fn rounds_as_moves(&self, moves: &[usize]) -> Vec<Round> {
+        return self
             .vals
             .iter()
-            .fold(0, |total_score, &(their_play, our_play)| {
-                let mut round_score = 1 + our_play;
+            .map(|&(theirs, letter)| Round {
+                theirs,
+                ours: moves[letter],
+                outcome: self.game.outcome(moves[letter], theirs),
+                score: self.game.score(moves[letter], theirs),
+            })
+            .collect();
+    }
 
-                if their_play == our_play {
-                    round_score += 3;
-                } else if (their_play + 1) % 3 == our_play {
-                    round_score += 6;
-                }
+    /// The total score if our letter `i` means playing `moves[i]`.
+    pub(crate) fn score_as_moves(&self, moves: &[usize]) -> i32 {
+        return self
+            .rounds_as_moves(moves)
+            .iter()
+            .map(|round| round.score)
+            .sum();
+    }
 
-                return total_score + round_score;
-            });
+    /// The total score if we picked the best move every round, ignoring our letters.
+    #[allow(dead_code)]
+    pub(crate) fn best_possible_score(&self) -> i32 {
+        return self
+            .vals
+            .iter()
+            .map(|&(theirs, _)| {
+                return (0..self.game.moves())
+                    .map(|ours| self.game.score(ours, theirs))
+                    .max()
+                    .unwrap();
+            })
+            .sum();
+    }
 
-        return score.to_string();
+    /// The reading of our letters as distinct moves that scores the most, along with its score.
+    #[allow(dead_code)]
+    pub(crate) fn best_move_mapping(&self) -> (Vec<usize>, i32) {
+        return arrangements(self.game.moves(), self.our_letters)
+            .into_iter()
+            .map(|moves| {
+                let score = self.score_as_moves(&moves);
+                return (moves, score);
+            })
+            .max_by_key(|(_, score)| *score)
+            .unwrap();
     }
 
-    fn part_2(&mut self) -> String {
-        let score = self
+    /// The total score if our letter `i` means we must achieve `outcomes[i]`.
+    pub(crate) fn score_as_outcomes(&self, outcomes: &[Outcome]) -> i32 {
+        return self
             .vals
             .iter()
-            .fold(0, |total_score, &(their_play, outcome)| {
-                let mut round_score = 1;
-
-                match outcome {
-                    0 => round_score += (their_play - 1).rem_euclid(3),
-                    1 => round_score += 3 + their_play,
-                    2 => round_score += 6 + (their_play + 1) % 3,
-                    _ => panic!(),
-                }
+            .map(|&(their_play, letter)| {
+                let our_play = self.game.response(their_play, outcomes[letter]);
+                return self.game.score(our_play, their_play);
+            })
+            .sum();
+    }
+}
 
-                return total_score + round_score;
-            });
+impl super::Day for Day02 {
+    fn part_1(&mut self) -> String {
+        let moves: Vec<usize> = (0..self.game.moves()).collect();
+        return self.score_as_moves(&moves).to_string();
+    }
 
-        return score.to_string();
+    fn part_2(&mut self) -> String {
+        return self
+            .score_as_outcomes(&[Outcome::Loss, Outcome::Draw, Outcome::Win])
+            .to_string();
     }
 }
 
@@ -92,4 +260,51 @@ mod test {
         let mut day = Day02::new();
         assert_eq!(day.part_2(), "12");
     }
+
+    #[test]
+    fn lizard_spock() {
+        let game = Game::rock_paper_scissors_lizard_spock();
+        let [rock, spock, paper, lizard, scissors] = [0, 1, 2, 3, 4];
+        assert_eq!(game.outcome(spock, scissors), Outcome::Win);
+        assert_eq!(game.outcome(lizard, spock), Outcome::Win);
+        assert_eq!(game.outcome(rock, lizard), Outcome::Win);
+        assert_eq!(game.outcome(paper, scissors), Outcome::Loss);
+        assert_eq!(game.response(rock, Outcome::Win), paper);
+        assert!(Game::new(vec![1, 2], [0, 3, 6]).is_err());
+        assert!(Game::new(vec![1], [0, 3, 6]).is_err());
+        assert!(Game::new(vec![], [0, 3, 6]).is_err());
+    }
+
+    #[test]
+    fn analysis() {
+        let day = Day02::new();
+        let rounds = day.rounds_as_moves(&[0, 1, 2]);
+        assert_eq!(
+            rounds.iter().map(|round| round.score).collect::<Vec<_>>(),
+            vec![8, 1, 6]
+        );
+        assert_eq!(outcome_counts(&rounds), [1, 1, 1]);
+        assert_eq!(day.best_possible_score(), 24);
+        assert_eq!(day.best_move_mapping(), (vec![2, 1, 0], 24));
+    }
+
+    #[test]
+    fn rules() {
+        let rules = |their_letters, our_letters| {
+            return Day02::with_rules(Game::rock_paper_scissors(), their_letters, our_letters);
+        };
+        assert_eq!(
+            rules("AB", "XYZ").err(),
+            Some("Expected a letter for each of their 3 moves, found \"AB\"".to_string())
+        );
+        assert_eq!(
+            rules("ABC", "WXYZ").err(),
+            Some("Expected at most 3 of our letters, one per move, found \"WXYZ\"".to_string())
+        );
+        assert_eq!(
+            rules("ABC", "XY").err(),
+            Some("line 3: Unrecognized player play: C Z".to_string())
+        );
+        assert!(rules("CBA", "ZYX").is_ok());
+    }
 }
@@ -1,70 +1,130 @@
-pub(crate) struct Day03 {
-    vals: Vec<Vec<u8>>,
-}
+use std::collections::HashMap;
+
+use crate::utils::bitset::BitSet;
 
-fn str_to_priorities(str: &str) -> Vec<u8> {
-    return str
-        .as_bytes()
-        .iter()
-        .map(|byte| match *byte {
-            65..=90 => *byte - 65 + 26,
-            97..=122 => *byte - 97,
-            _ => panic!("Unrecognized priority in {}", str),
-        })
-        .collect();
+/// The items that can be packed in a rucksack, and how much each one is worth.
+pub(crate) struct Alphabet {
+    items: Vec<(char, u32)>,
+    indexes: HashMap<char, usize>,
 }
 
-impl Day03 {
-    pub(crate) fn new() -> Self {
-        // convert the item labels a..zA..Z to their priority (0-indexed)
-        let vals = crate::utils::read_day_lines(3)
+impl Alphabet {
+    pub(crate) fn new(items: impl IntoIterator<Item = (char, u32)>) -> Self {
+        let items: Vec<(char, u32)> = items.into_iter().collect();
+        let indexes = items
             .iter()
-            .map(|val| str_to_priorities(val))
+            .enumerate()
+            .map(|(i, (item, _))| (*item, i))
             .collect();
-        return Self { vals };
+        return Self { items, indexes };
+    }
+
+    /// The puzzle's items, a..z worth 1..26 and A..Z worth 27..52.
+    pub(crate) fn standard() -> Self {
+        return Self::new(('a'..='z').chain('A'..='Z').zip(1..));
+    }
+
+    fn item(&self, index: usize) -> char {
+        return self.items[index].0;
+    }
+
+    fn priority(&self, index: usize) -> u32 {
+        return self.items[index].1;
     }
 }
 
-impl super::Day for Day03 {
-    fn part_1(&mut self) -> String {
+pub(crate) struct Day03 {
+    alphabet: Alphabet,
+    // each rucksack as indexes into the alphabet
+    vals: Vec<Vec<usize>>,
+}
+
+impl Day03 {
+    pub(crate) fn new() -> Self {
+        return Self::with_alphabet(Alphabet::standard()).unwrap();
+    }
+
+    pub(crate) fn with_alphabet(alphabet: Alphabet) -> Result<Self, String> {
+        let vals = crate::utils::stream_day_lines(3)
+            .enumerate()
+            .map(|(line, val)| {
+                return val
+                    .chars()
+                    .map(|item| {
+                        return alphabet.indexes.get(&item).copied().ok_or(format!(
+                            "Unrecognized item {} on line {}",
+                            item,
+                            line + 1
+                        ));
+                    })
+                    .collect::<Result<Vec<_>, _>>();
+            })
+            .collect::<Result<_, _>>()?;
+        return Ok(Self { alphabet, vals });
+    }
+
+    /// Every item found in both compartments of each rucksack.
+    pub(crate) fn duplicates(&self) -> Vec<Vec<char>> {
         return self
             .vals
             .iter()
-            .fold(0, |acc, vals| {
+            .map(|vals| {
                 let (left, right) = vals.split_at(vals.len() / 2);
+                let left: BitSet = left.iter().copied().collect();
+                let right: BitSet = right.iter().copied().collect();
+                return (&left & &right)
+                    .iter()
+                    .map(|index| self.alphabet.item(index))
+                    .collect();
+            })
+            .collect();
+    }
+
+    /// Every item carried by all rucksacks in each group of `group_size` rucksacks.
+    pub(crate) fn badges(&self, group_size: usize) -> Result<Vec<Vec<char>>, String> {
+        if group_size == 0 || !self.vals.len().is_multiple_of(group_size) {
+            return Err(format!(
+                "{} rucksacks can't be split into groups of {}",
+                self.vals.len(),
+                group_size
+            ));
+        }
 
-                // map of seen items from the left side of the bag, to check for on the right side.
-                let mut seen = [false; 52];
-                for priority in left {
-                    seen[*priority as usize] = true;
-                }
-                for priority in right {
-                    if seen[*priority as usize] {
-                        return acc + (*priority) as u32 + 1;
-                    }
-                }
-                panic!("No duplicate priority found in: {:?},{:?}", left, right);
+        return Ok(self
+            .vals
+            .chunks_exact(group_size)
+            .map(|chunk| {
+                // intersect the sets of items in each bag to find the exclusive items in the group
+                let matched = chunk
+                    .iter()
+                    .map(|bag| bag.iter().copied().collect::<BitSet>())
+                    .reduce(|matched, bag| &matched & &bag)
+                    .unwrap();
+                return matched
+                    .iter()
+                    .map(|index| self.alphabet.item(index))
+                    .collect();
             })
-            .to_string();
+            .collect());
+    }
+
+    fn total_priority(&self, items: &[Vec<char>]) -> u32 {
+        return items
+            .iter()
+            .flatten()
+            .map(|item| self.alphabet.priority(self.alphabet.indexes[item]))
+            .sum();
+    }
+}
+
+impl super::Day for Day03 {
+    fn part_1(&mut self) -> String {
+        return self.total_priority(&self.duplicates()).to_string();
     }
 
     fn part_2(&mut self) -> String {
-        let sum = self.vals.chunks_exact(3).fold(0, |acc, chunk| {
-            let matched_bit = chunk.iter().fold(u64::MAX, |matched_bits, bag| {
-                // convert each priority into its relative power of 2, and create a bitmask of all items in the bag
-                let bag_bits = bag.iter().fold(0, |bits, item| {
-                    let w = u64::pow(2, (*item) as u32);
-                    return bits | w;
-                });
-                // AND the bitmasks to find the exclusive item in the bag
-                return matched_bits & bag_bits;
-            });
-
-            // get the priority back from the bitmask by finding the 1s position
-            return acc + (64 - matched_bit.leading_zeros());
-        });
-
-        return sum.to_string();
+        let badges = self.badges(3).unwrap_or_else(|error| panic!("{}", error));
+        return self.total_priority(&badges).to_string();
     }
 }
 
@@ -84,4 +144,21 @@ mod test {
         let mut day = Day03::new();
         assert_eq!(day.part_2(), "70");
     }
+
+    #[test]
+    fn group_sizes() {
+        let day = Day03::new();
+        assert_eq!(day.badges(3).unwrap(), vec![vec!['r'], vec!['Z']]);
+        assert_eq!(day.badges(2).unwrap()[2], vec!['G', 'J', 'Z']);
+        assert!(day.badges(4).is_err());
+    }
+
+    #[test]
+    fn custom_alphabet() {
+        assert!(Day03::with_alphabet(Alphabet::new([('a', 1)])).is_err());
+
+        let alphabet = Alphabet::new(('a'..='z').chain('A'..='Z').map(|item| (item, 1)));
+        let mut day = Day03::with_alphabet(alphabet).unwrap();
+        assert_eq!(day.part_1(), "6");
+    }
 }
@@ -1,13 +1,15 @@
 use crate::utils::read_day_csv_lines;
 
+type Section = u32;
+
 struct Assignment {
-    lower_bound: u8,
-    upper_bound: u8,
+    lower_bound: Section,
+    upper_bound: Section,
 }
 
 impl Assignment {
     fn new(str: &String) -> Self {
-        let bounds: Vec<u8> = str.split("-").map(|part| part.parse().unwrap()).collect();
+        let bounds: Vec<Section> = str.split("-").map(|part| part.parse().unwrap()).collect();
 
         return Self {
             lower_bound: bounds[0],
@@ -23,6 +25,15 @@ impl Assignment {
         return (self.lower_bound >= rhs.lower_bound && self.lower_bound <= rhs.upper_bound)
             || (self.upper_bound <= rhs.upper_bound && self.upper_bound >= rhs.lower_bound);
     }
+
+    fn overlap(self: &Assignment, rhs: &Assignment) -> Section {
+        let lower = self.lower_bound.max(rhs.lower_bound);
+        let upper = self.upper_bound.min(rhs.upper_bound);
+        if lower > upper {
+            return 0;
+        }
+        return upper - lower + 1;
+    }
 }
 
 pub(crate) struct Day04 {
@@ -39,6 +50,131 @@ impl Day04 {
     }
 }
 
+// analytics across every assignment in the input, which aren't needed for either part
+impl Day04 {
+    // the change in how many elves cover each section, in section order. Ends are stored one
+    // past the upper bound, as a u64 so that the last section can still end.
+    fn sweep(&self) -> Vec<(u64, i64)> {
+        let mut events: Vec<(u64, i64)> = self
+            .vals
+            .iter()
+            .flat_map(|(lhs, rhs)| [lhs, rhs])
+            .flat_map(|assignment| {
+                [
+                    (u64::from(assignment.lower_bound), 1),
+                    (u64::from(assignment.upper_bound) + 1, -1),
+                ]
+            })
+            .collect();
+        events.sort_unstable();
+
+        // merge the events at each section, so the running total is only read between sections
+        let mut merged: Vec<(u64, i64)> = vec![];
+        for (section, change) in events {
+            match merged.last_mut() {
+                Some(last) if last.0 == section => last.1 += change,
+                _ => merged.push((section, change)),
+            }
+        }
+        return merged;
+    }
+
+    /// The most elves assigned to any one section, and the first section with that many.
+    pub(crate) fn busiest_section(&self) -> Option<(usize, Section)> {
+        let mut elves = 0;
+        let mut busiest: Option<(usize, Section)> = None;
+        for (section, change) in self.sweep() {
+            elves += change;
+            if busiest.is_none_or(|(count, _)| elves as usize > count) {
+                busiest = Some((elves as usize, section as Section));
+            }
+        }
+        return busiest;
+    }
+
+    /// The inclusive ranges of sections between the first and last assigned that nobody covers.
+    pub(crate) fn uncovered_sections(&self) -> Vec<(Section, Section)> {
+        let sweep = self.sweep();
+        let mut elves = 0;
+        let mut uncovered = vec![];
+        for window in sweep.windows(2) {
+            elves += window[0].1;
+            if elves == 0 {
+                uncovered.push((window[0].0 as Section, (window[1].0 - 1) as Section));
+            }
+        }
+        return uncovered;
+    }
+
+    /// The two assignments on different lines that share the most sections, as how many they
+    /// share and where each one is, by line index and side (0 for the left, 1 for the right).
+    pub(crate) fn largest_overlap(&self) -> Option<(Section, [(usize, usize); 2])> {
+        let mut assignments: Vec<((usize, usize), &Assignment)> = self
+            .vals
+            .iter()
+            .enumerate()
+            .flat_map(|(line, (lhs, rhs))| [((line, 0), lhs), ((line, 1), rhs)])
+            .collect();
+        assignments.sort_by_key(|(_, assignment)| assignment.lower_bound);
+
+        // Each assignment overlaps most with the earlier one that reaches furthest, so only the
+        // furthest reaching from two different lines need keeping, furthest first.
+        let mut furthest: Vec<((usize, usize), &Assignment)> = vec![];
+        let mut largest: Option<(Section, [(usize, usize); 2])> = None;
+        for (position, assignment) in assignments {
+            if let Some((other, earlier)) = furthest.iter().find(|(other, _)| other.0 != position.0)
+            {
+                let overlap = assignment.overlap(earlier);
+                if overlap > 0 && largest.is_none_or(|(most, _)| overlap > most) {
+                    largest = Some((overlap, [*other, position]));
+                }
+            }
+
+            furthest.retain(|(other, earlier)| {
+                return other.0 != position.0 || earlier.upper_bound >= assignment.upper_bound;
+            });
+            if furthest.iter().all(|(other, _)| other.0 != position.0) {
+                furthest.push((position, assignment));
+                furthest.sort_by_key(|(_, reaching)| std::cmp::Reverse(reaching.upper_bound));
+                furthest.truncate(2);
+            }
+        }
+        return largest;
+    }
+
+    /// The busiest section, the uncovered sections and the largest overlap, one per line.
+    pub(crate) fn sweep_report(&self) -> String {
+        let busiest = match self.busiest_section() {
+            Some((elves, section)) => format!("{} elves share section {}", elves, section),
+            None => "No sections are assigned".to_string(),
+        };
+        let uncovered: Vec<String> = self
+            .uncovered_sections()
+            .iter()
+            .map(|(lower, upper)| format!("{}-{}", lower, upper))
+            .collect();
+        let overlap = match self.largest_overlap() {
+            Some((sections, [lhs, rhs])) => format!(
+                "The largest overlap is {} sections, between line {} and line {}",
+                sections,
+                lhs.0 + 1,
+                rhs.0 + 1
+            ),
+            None => "No assignments on different lines overlap".to_string(),
+        };
+        return format!(
+            "{}\nUncovered sections: {}\n{}",
+            busiest,
+            if uncovered.is_empty() {
+                "none".to_string()
+            } else {
+                uncovered.join(", ")
+            },
+            overlap
+        );
+    }
+}
+
 impl super::Day for Day04 {
     fn part_1(&mut self) -> String {
         let mut count = 0;
@@ -77,4 +213,39 @@ mod test {
         let mut day = Day04::new();
         assert_eq!(day.part_2(), "4");
     }
+
+    #[test]
+    fn sweep() {
+        let day = Day04::new();
+        assert_eq!(day.busiest_section(), Some((8, 6)));
+        assert_eq!(day.uncovered_sections(), vec![]);
+        assert_eq!(day.largest_overlap(), Some((5, [(3, 0), (5, 0)])));
+        assert_eq!(
+            day.sweep_report(),
+            "8 elves share section 6\nUncovered sections: none\nThe largest overlap is 5 sections, between line 4 and line 6"
+        );
+
+        let gappy = Day04 {
+            vals: vec![(
+                Assignment::new(&"1-2".to_string()),
+                Assignment::new(&"5-6".to_string()),
+            )],
+        };
+        assert_eq!(gappy.uncovered_sections(), vec![(3, 4)]);
+        assert_eq!(gappy.largest_overlap(), None);
+
+        // the best overlap is across lines, even though each line's own pair overlaps too
+        let crossed = Day04 {
+            vals: ["1-9", "8-20", "2-3", "5-30", "1-1", "10-10"]
+                .chunks(2)
+                .map(|pair| {
+                    return (
+                        Assignment::new(&pair[0].to_string()),
+                        Assignment::new(&pair[1].to_string()),
+                    );
+                })
+                .collect(),
+        };
+        assert_eq!(crossed.largest_overlap(), Some((13, [(1, 1), (0, 1)])));
+    }
 }
@@ -1,100 +1,298 @@
 pub(crate) struct Day05 {
-    stacks: Box<[Vec<char>]>,
+    stacks: Box<[Vec<String>]>,
     instructions: Vec<Instruction>,
 }
 
 struct Instruction {
+    line: usize,
     count: usize,
     from: usize,
     to: usize,
 }
 
-impl Day05 {
-    pub(crate) fn new() -> Self {
-        let mut vals = crate::utils::read_day_grouped_lines(5);
-        let column_defs = vals[0].pop().unwrap();
-        let column_count = column_defs
-            .split_ascii_whitespace()
-            .next_back()
-            .unwrap()
-            .parse::<usize>()
-            .unwrap();
-
-        let mut stacks: Vec<Vec<char>> = vec![];
-        stacks.resize(column_count, vec![]);
-
-        vals[0].reverse();
-        for row in &vals[0] {
-            let mut chars = row.chars();
-            chars.next();
-
-            for i in 0..column_count {
-                let char = chars.next().unwrap();
-                if char != ' ' {
-                    stacks[i].push(char);
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CraneModel {
+    /// Moves crates one at a time, reversing their order.
+    CrateMover9000,
+    /// Moves all the crates at once, keeping their order.
+    CrateMover9001,
+}
+
+impl CraneModel {
+    fn apply(&self, stacks: &mut [Vec<String>], instruction: &Instruction) {
+        // moving crates back onto the stack they came from leaves it as it was, in either order
+        if instruction.from == instruction.to {
+            return;
+        }
+        let from = &mut stacks[instruction.from];
+        let mut moved = from.split_off(from.len() - instruction.count);
+        if *self == CraneModel::CrateMover9000 {
+            moved.reverse();
+        }
+        stacks[instruction.to].extend(moved);
+    }
+}
+
+/// Draws the stacks the way the puzzle does, with the stack numbers underneath.
+#[allow(dead_code)]
+pub(crate) fn render_stacks(stacks: &[Vec<String>]) -> String {
+    let width = stacks
+        .iter()
+        .flatten()
+        .map(|label| label.chars().count() + 2)
+        .chain((1..=stacks.len()).map(|number| number.to_string().len()))
+        .max()
+        .unwrap_or(3);
+    let height = stacks.iter().map(Vec::len).max().unwrap_or(0);
+
+    let mut rows: Vec<String> = (0..height)
+        .rev()
+        .map(|level| {
+            return stacks
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(label) => format!("{:<width$}", format!("[{}]", label), width = width),
+                    None => " ".repeat(width),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+        })
+        .collect();
+    rows.push(
+        (1..=stacks.len())
+            .map(|number| format!("{:^width$}", number, width = width))
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    return rows.join("\n");
+}
+
+// the byte ranges of each whitespace separated token in a line
+fn token_spans(line: &str) -> Vec<(usize, usize)> {
+    let mut spans = vec![];
+    let mut start = None;
+    for (i, char) in line.char_indices().chain([(line.len(), ' ')]) {
+        match (start, char.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(token_start), true) => {
+                spans.push((token_start, i));
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    return spans;
+}
+
+fn parse_stacks(lines: &[String]) -> Result<Vec<Vec<String>>, String> {
+    let (footer, rows) = lines.split_last().ok_or("Missing crate diagram")?;
+
+    // stacks are identified by where their number sits in the footer, rather than a fixed width
+    let columns = token_spans(footer);
+    for (i, (start, end)) in columns.iter().enumerate() {
+        if footer[*start..*end] != (i + 1).to_string() {
+            return Err(format!(
+                "line {}, column {}: expected stack number {}, found {:?}",
+                lines.len(),
+                start + 1,
+                i + 1,
+                &footer[*start..*end]
+            ));
+        }
+    }
+
+    let mut stacks: Vec<Vec<String>> = vec![vec![]; columns.len()];
+    // whether a stack has had a row without a crate, so any crate above it would be floating
+    let mut topped = vec![false; columns.len()];
+    for (row_index, row) in rows.iter().enumerate().rev() {
+        let line = row_index + 1;
+        let mut filled = vec![false; columns.len()];
+
+        for (start, end) in token_spans(row) {
+            let token = &row[start..end];
+            let label = token
+                .strip_prefix('[')
+                .and_then(|token| token.strip_suffix(']'))
+                .filter(|label| !label.is_empty())
+                .ok_or(format!(
+                    "line {}, column {}: expected a crate like [A], found {:?}",
+                    line,
+                    start + 1,
+                    token
+                ))?;
+
+            let mut matching = columns
+                .iter()
+                .enumerate()
+                .filter(|(_, (column_start, column_end))| {
+                    *column_start < end && start < *column_end
+                })
+                .map(|(stack, _)| stack);
+            let stack = match (matching.next(), matching.next()) {
+                (Some(stack), None) => stack,
+                (None, _) => {
+                    return Err(format!(
+                        "line {}, column {}: crate {} isn't above any stack number",
+                        line,
+                        start + 1,
+                        token
+                    ))
+                }
+                (Some(_), Some(_)) => {
+                    return Err(format!(
+                        "line {}, column {}: crate {} is above more than one stack number",
+                        line,
+                        start + 1,
+                        token
+                    ))
                 }
+            };
 
-                chars.next();
-                chars.next();
-                chars.next();
+            if filled[stack] {
+                return Err(format!(
+                    "line {}, column {}: crate {} is in the same row of stack {} as another crate",
+                    line,
+                    start + 1,
+                    token,
+                    stack + 1
+                ));
+            }
+            if topped[stack] {
+                return Err(format!(
+                    "line {}, column {}: crate {} is floating above an empty space in stack {}",
+                    line,
+                    start + 1,
+                    token,
+                    stack + 1
+                ));
             }
+            filled[stack] = true;
+            stacks[stack].push(label.to_string());
         }
 
-        let instructions = vals[1]
+        for (stack, filled) in filled.iter().enumerate() {
+            topped[stack] |= !filled;
+        }
+    }
+
+    return Ok(stacks);
+}
+
+fn parse_instruction(line: usize, str: &str, stack_count: usize) -> Result<Instruction, String> {
+    let malformed = || {
+        return format!(
+            "line {}: expected an instruction like \"move 1 from 2 to 3\", found {:?}",
+            line, str
+        );
+    };
+    let parts: Vec<_> = str.split_whitespace().collect();
+    if parts.len() != 6 || parts[0] != "move" || parts[2] != "from" || parts[4] != "to" {
+        return Err(malformed());
+    }
+
+    let stack = |part: &str| {
+        let number: usize = part.parse().map_err(|_| malformed())?;
+        if number == 0 || number > stack_count {
+            return Err(format!("line {}: there is no stack {}", line, number));
+        }
+        return Ok(number - 1);
+    };
+    return Ok(Instruction {
+        line,
+        count: parts[1].parse().map_err(|_| malformed())?,
+        from: stack(parts[3])?,
+        to: stack(parts[5])?,
+    });
+}
+
+impl Day05 {
+    pub(crate) fn new() -> Self {
+        let vals = crate::utils::read_day_grouped_lines(5);
+        return Self::parse(&vals).unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    fn parse(vals: &[Vec<String>]) -> Result<Self, String> {
+        let (diagram, instructions) = match vals {
+            [diagram, instructions] => (diagram, instructions),
+            _ => {
+                return Err(format!(
+                    "Expected a crate diagram and instructions separated by a blank line, found {} sections",
+                    vals.len()
+                ))
+            }
+        };
+
+        let stacks = parse_stacks(diagram)?;
+        let instructions = instructions
             .iter()
-            .map(|val| {
-                let parts: Vec<_> = val.split_whitespace().collect();
-                return Instruction {
-                    count: parts[1].parse().unwrap(),
-                    from: parts[3].parse::<usize>().unwrap() - 1,
-                    to: parts[5].parse::<usize>().unwrap() - 1,
-                };
-            })
-            .collect::<Vec<Instruction>>();
-
-        return Self {
+            .enumerate()
+            .map(|(i, val)| parse_instruction(diagram.len() + i + 2, val, stacks.len()))
+            .collect::<Result<Vec<Instruction>, String>>()?;
+
+        return Ok(Self {
             stacks: stacks.into_boxed_slice(),
             instructions,
-        };
+        });
+    }
+
+    /// Checks every instruction only moves crates that will be there, whichever crane is used.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let mut heights: Vec<usize> = self.stacks.iter().map(Vec::len).collect();
+        for instruction in &self.instructions {
+            if heights[instruction.from] < instruction.count {
+                return Err(format!(
+                    "line {}: can't move {} crates from stack {}, which only has {}",
+                    instruction.line,
+                    instruction.count,
+                    instruction.from + 1,
+                    heights[instruction.from]
+                ));
+            }
+            heights[instruction.from] -= instruction.count;
+            heights[instruction.to] += instruction.count;
+        }
+        return Ok(());
+    }
+
+    /// The stacks after each instruction in turn, leaving these stacks untouched.
+    #[allow(dead_code)]
+    pub(crate) fn states(
+        &self,
+        model: CraneModel,
+    ) -> Result<impl Iterator<Item = Vec<Vec<String>>> + '_, String> {
+        self.validate()?;
+        let mut stacks = self.stacks.to_vec();
+        return Ok(self.instructions.iter().map(move |instruction| {
+            model.apply(&mut stacks, instruction);
+            return stacks.clone();
+        }));
+    }
+
+    fn run(&mut self, model: CraneModel) -> String {
+        self.validate().unwrap_or_else(|error| panic!("{}", error));
+        for instruction in &self.instructions {
+            model.apply(&mut self.stacks, instruction);
+        }
+
+        return self.get_output();
     }
 
     fn get_output(&self) -> String {
         return self
             .stacks
             .iter()
-            .map(|stack| stack.last().unwrap())
+            .filter_map(|stack| stack.last().map(String::as_str))
             .collect::<String>();
     }
 }
 
 impl super::Day for Day05 {
     fn part_1(&mut self) -> String {
-        for instruction in &self.instructions {
-            for _ in 0..instruction.count {
-                let char = self.stacks[instruction.from].pop().unwrap();
-                self.stacks[instruction.to].push(char);
-            }
-        }
-
-        return self.get_output();
+        return self.run(CraneModel::CrateMover9000);
     }
 
     fn part_2(&mut self) -> String {
-        let mut tmp: Vec<char> = vec![];
-        tmp.reserve(32);
-
-        for instruction in &self.instructions {
-            for _ in 0..instruction.count {
-                let char = self.stacks[instruction.from].pop().unwrap();
-                tmp.push(char);
-            }
-            for _ in 0..instruction.count {
-                let char = tmp.pop().unwrap();
-                self.stacks[instruction.to].push(char);
-            }
-        }
-
-        return self.get_output();
+        return self.run(CraneModel::CrateMover9001);
     }
 }
 
@@ -114,4 +312,60 @@ mod test {
         let mut day = Day05::new();
         assert_eq!(day.part_2(), "MCD");
     }
+
+    fn lines(str: &str) -> Vec<Vec<String>> {
+        return str
+            .split("\n\n")
+            .map(|group| group.lines().map(String::from).collect())
+            .collect();
+    }
+
+    #[test]
+    fn wide_diagram() {
+        let mut day = Day05::parse(&lines(
+            "[A] [B] [C] [D] [E] [F] [G] [H] [I] [JK]\n 1   2   3   4   5   6   7   8   9   10\n\nmove 1 from 10 to 1",
+        ))
+        .unwrap();
+        assert_eq!(day.part_1(), "JKBCDEFGHI");
+    }
+
+    #[test]
+    fn malformed_diagram() {
+        let error = Day05::parse(&lines("[A]\n    [B]\n 1   2\n\nmove 1 from 1 to 2"));
+        assert_eq!(
+            error.err(),
+            Some(
+                "line 1, column 1: crate [A] is floating above an empty space in stack 1"
+                    .to_string()
+            )
+        );
+
+        let error = Day05::parse(&lines("[A]\n 1\n\nmove 1 from 1 to 2"));
+        assert_eq!(error.err(), Some("line 4: there is no stack 2".to_string()));
+    }
+
+    #[test]
+    fn replay() {
+        let day = Day05::new();
+        let states: Vec<_> = day.states(CraneModel::CrateMover9000).unwrap().collect();
+        assert_eq!(states.len(), 4);
+        assert_eq!(
+            render_stacks(&states[0]),
+            "[D]        \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 "
+        );
+
+        let error = Day05::parse(&lines("[A]\n 1   2\n\nmove 2 from 1 to 2")).unwrap();
+        assert_eq!(
+            error.validate(),
+            Err("line 4: can't move 2 crates from stack 1, which only has 1".to_string())
+        );
+    }
+
+    #[test]
+    fn move_onto_same_stack() {
+        let mut day = Day05::parse(&lines("[C]\n[B]\n[A]\n 1 \n\nmove 2 from 1 to 1")).unwrap();
+        assert_eq!(day.part_1(), "C");
+        let states: Vec<_> = day.states(CraneModel::CrateMover9000).unwrap().collect();
+        assert_eq!(states[0][0], vec!["A", "B", "C"]);
+    }
 }
@@ -1,38 +1,185 @@
-use std::collections::HashSet;
+use std::{collections::VecDeque, io::BufRead};
 
-pub(crate) struct Day06 {
-    lines: Vec<String>,
+/// A run of `len` distinct bytes ending just before `offset` on the (zero based) `line`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Marker {
+    pub(crate) line: usize,
+    pub(crate) len: usize,
+    pub(crate) offset: usize,
 }
 
+// the last `len` bytes seen, with how often each byte appears so checking for a marker is O(1)
+struct Window {
+    len: usize,
+    recent: VecDeque<u8>,
+    counts: [usize; 256],
+    // how many distinct bytes appear more than once in the window
+    repeated: usize,
+}
+
+impl Window {
+    fn new(len: usize) -> Self {
+        return Self {
+            len,
+            recent: VecDeque::with_capacity(len + 1),
+            counts: [0; 256],
+            repeated: 0,
+        };
+    }
+
+    // adds a byte, returning whether the window is now a marker
+    fn push(&mut self, byte: u8) -> bool {
+        self.recent.push_back(byte);
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.repeated += 1;
+        }
+
+        if self.recent.len() > self.len {
+            let old = self.recent.pop_front().unwrap();
+            self.counts[old as usize] -= 1;
+            if self.counts[old as usize] == 1 {
+                self.repeated -= 1;
+            }
+        }
+        return self.recent.len() == self.len && self.repeated == 0;
+    }
+
+    fn clear(&mut self) {
+        for byte in self.recent.drain(..) {
+            self.counts[byte as usize] = 0;
+        }
+        self.repeated = 0;
+    }
+}
+
+/// Every marker of each of the `lens` in a byte stream, found in a single pass. Each line is its
+/// own datastream, so windows never span a newline.
+pub(crate) struct Markers<R> {
+    reader: R,
+    windows: Vec<Window>,
+    line: usize,
+    offset: usize,
+    // markers found at the last byte read that haven't been yielded yet
+    pending: VecDeque<Marker>,
+}
+
+pub(crate) fn markers<R: BufRead>(reader: R, lens: &[usize]) -> Markers<R> {
+    return Markers {
+        reader,
+        windows: lens.iter().map(|len| Window::new(*len)).collect(),
+        line: 0,
+        offset: 0,
+        pending: VecDeque::new(),
+    };
+}
+
+impl<R: BufRead> Markers<R> {
+    /// How many lines have been started so far.
+    pub(crate) fn lines_read(&self) -> usize {
+        return self.line + usize::from(self.offset > 0);
+    }
+
+    /// Moves on to the next line without looking at the rest of this one.
+    pub(crate) fn skip_line(&mut self) {
+        self.reader.skip_until(b'\n').unwrap();
+        self.pending.clear();
+        self.end_line();
+    }
+
+    fn end_line(&mut self) {
+        self.windows.iter_mut().for_each(Window::clear);
+        self.line += 1;
+        self.offset = 0;
+    }
+}
+
+impl<R: BufRead> Iterator for Markers<R> {
+    type Item = Marker;
+
+    fn next(&mut self) -> Option<Marker> {
+        loop {
+            if let Some(marker) = self.pending.pop_front() {
+                return Some(marker);
+            }
+
+            let buffer = self.reader.fill_buf().unwrap();
+            if buffer.is_empty() {
+                return None;
+            }
+            // work through the buffered bytes until one of them ends a marker
+            let mut consumed = 0;
+            for byte in buffer.iter().copied() {
+                consumed += 1;
+                if byte == b'\n' {
+                    self.windows.iter_mut().for_each(Window::clear);
+                    self.line += 1;
+                    self.offset = 0;
+                    continue;
+                }
+
+                self.offset += 1;
+                for window in self.windows.iter_mut() {
+                    if window.push(byte) {
+                        self.pending.push_back(Marker {
+                            line: self.line,
+                            len: window.len,
+                            offset: self.offset,
+                        });
+                    }
+                }
+                if !self.pending.is_empty() {
+                    break;
+                }
+            }
+            self.reader.consume(consumed);
+        }
+    }
+}
+
+/// The offset of the first marker of each of the `lens` on every line, in one pass. Once every
+/// length has been found on a line, the rest of it is skipped.
+pub(crate) fn first_markers(reader: impl BufRead, lens: &[usize]) -> Vec<Vec<Option<usize>>> {
+    let mut markers = markers(reader, lens);
+    let mut firsts: Vec<Vec<Option<usize>>> = vec![];
+    while let Some(marker) = markers.next() {
+        if firsts.len() <= marker.line {
+            firsts.resize(marker.line + 1, vec![None; lens.len()]);
+        }
+        for (index, len) in lens.iter().enumerate() {
+            if *len == marker.len {
+                firsts[marker.line][index].get_or_insert(marker.offset);
+            }
+        }
+        if firsts[marker.line].iter().all(Option::is_some) {
+            markers.skip_line();
+        }
+    }
+    // lines without any markers still get an entry
+    firsts.resize(markers.lines_read(), vec![None; lens.len()]);
+    return firsts;
+}
+
+pub(crate) struct Day06 {}
+
 impl Day06 {
     pub(crate) fn new() -> Self {
-        let lines = super::super::utils::read_day_lines(6);
-        return Self { lines };
+        return Self {};
     }
 
     fn find_markers(&mut self, marker_len: usize) -> String {
-        let x = self
-            .lines
+        return first_markers(crate::utils::open_day(6), &[marker_len])
             .iter()
-            .map(|line| {
-                return line
-                    .chars()
-                    .collect::<Vec<char>>()
-                    .windows(marker_len)
-                    .enumerate()
-                    .find_map(|(i, window)| {
-                        let mut unique = HashSet::new();
-                        if window.iter().all(|char| unique.insert(*char)) {
-                            return Some(i + marker_len);
-                        } else {
-                            return None;
-                        }
+            .enumerate()
+            .map(|(line, firsts)| {
+                return firsts[0]
+                    .unwrap_or_else(|| {
+                        panic!("No marker of length {} on line {}", marker_len, line + 1)
                     })
-                    .unwrap()
                     .to_string();
             })
-            .collect::<Vec<_>>();
-        return x.join("\n").to_string();
+            .collect::<Vec<_>>()
+            .join("\n");
     }
 }
 
@@ -62,4 +209,36 @@ mod test {
         let mut day = Day06::new();
         assert_eq!(day.part_2(), "19\n23\n23\n29\n26");
     }
+
+    #[test]
+    fn every_marker() {
+        let found: Vec<_> = markers("abcab\naab".as_bytes(), &[3, 2])
+            .map(|marker| (marker.line, marker.len, marker.offset))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (0, 2, 2),
+                (0, 3, 3),
+                (0, 2, 3),
+                (0, 3, 4),
+                (0, 2, 4),
+                (0, 3, 5),
+                (0, 2, 5),
+                (1, 2, 3)
+            ]
+        );
+
+        let firsts = first_markers(crate::utils::open_day(6), &[4, 14]);
+        assert_eq!(firsts.len(), 5);
+        assert_eq!(firsts[0], vec![Some(7), Some(19)]);
+    }
+
+    #[test]
+    fn line_without_marker() {
+        let firsts = first_markers("abcd\naaaa".as_bytes(), &[4]);
+        assert_eq!(firsts, vec![vec![Some(4)], vec![None]]);
+        let firsts = first_markers("aaaa\nabcd\n".as_bytes(), &[4, 2]);
+        assert_eq!(firsts, vec![vec![None, None], vec![Some(4), Some(2)]]);
+    }
 }
@@ -1,143 +1,665 @@
-use std::{
-    cell::RefCell,
-    rc::{Rc, Weak},
+use std::{collections::HashSet, fmt::Display};
+
+use crate::utils::{
+    arena::{Id, Tree},
+    parse::{
+        delimited, from_fn, literal, parse_complete, separated, take_while, whitespace, Failure,
+        PResult, Parser,
+    },
 };
 
 #[derive(Debug)]
-struct File {
-    parent: Weak<RefCell<File>>,
-    children: Vec<Rc<RefCell<File>>>,
-    name: String,
-    size: u32,
-    is_directory: bool,
+pub(crate) struct File {
+    pub(crate) name: String,
+    // for directories, the total size of everything inside them
+    pub(crate) size: u32,
+    pub(crate) is_directory: bool,
+}
+
+/// Something in a transcript that doesn't fit with the rest of it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Anomaly {
+    /// A line that isn't a command we know or part of an `ls` listing.
+    Malformed { line: usize, text: String },
+    /// The same name appears twice in one listing.
+    DuplicateEntry { line: usize, name: String },
+    /// A name listed again with a different size, or as a file where it was a directory.
+    ConflictingEntry { line: usize, name: String },
+    /// `cd ..` from the root directory.
+    ParentOfRoot { line: usize },
+    /// `cd` into a directory that hasn't been listed.
+    UnknownDirectory { line: usize, name: String },
+    /// `cd` into something listed as a file.
+    NotADirectory { line: usize, name: String },
+}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Anomaly::Malformed { line, text } => {
+                return write!(f, "line {}: unexpected {:?}", line, text);
+            }
+            Anomaly::DuplicateEntry { line, name } => {
+                return write!(f, "line {}: {} is listed twice", line, name);
+            }
+            Anomaly::ConflictingEntry { line, name } => {
+                return write!(
+                    f,
+                    "line {}: {} doesn't match its earlier listing",
+                    line, name
+                );
+            }
+            Anomaly::ParentOfRoot { line } => {
+                return write!(f, "line {}: cd .. from the root directory", line);
+            }
+            Anomaly::UnknownDirectory { line, name } => {
+                return write!(
+                    f,
+                    "line {}: cd into {}, which hasn't been listed",
+                    line, name
+                );
+            }
+            Anomaly::NotADirectory { line, name } => {
+                return write!(f, "line {}: cd into {}, which is a file", line, name);
+            }
+        }
+    }
+}
+
+/// The filesystem pieced together from a terminal transcript.
+pub(crate) struct FileSystem {
+    tree: Tree<File>,
 }
 
-impl File {
+impl FileSystem {
+    pub(crate) fn new() -> Self {
+        return Self {
+            tree: Tree::new(File {
+                name: "/".to_string(),
+                size: 0,
+                is_directory: true,
+            }),
+        };
+    }
+
+    /// Replays the `$ cd` and `$ ls` commands in a transcript, ignoring any anomalies.
+    pub(crate) fn from_transcript(lines: &[String]) -> Self {
+        return Self::replay(lines).0;
+    }
+
+    /// Replays a transcript, noting anything inconsistent along the way. Replaying carries on
+    /// past each anomaly as sensibly as it can, and listing a directory again changes nothing.
+    pub(crate) fn replay(lines: &[String]) -> (Self, Vec<Anomaly>) {
+        let mut fs = Self::new();
+        let mut anomalies = vec![];
+        let mut curr = fs.root();
+        // the names in the `ls` output being read, if one is
+        let mut listing: Option<HashSet<String>> = None;
+
+        for (i, line) in lines.iter().enumerate() {
+            let line_number = i + 1;
+            let parts: Vec<&str> = line.split_ascii_whitespace().collect();
+            match parts.as_slice() {
+                ["$", "ls"] => listing = Some(HashSet::new()),
+                ["$", "cd", target] => {
+                    listing = None;
+                    if *target == ".." {
+                        match fs.tree.parent(curr) {
+                            Some(parent) => curr = parent,
+                            None => anomalies.push(Anomaly::ParentOfRoot { line: line_number }),
+                        }
+                    } else if *target == "/" {
+                        curr = fs.root();
+                    } else {
+                        match fs.child(curr, target) {
+                            Some(child) if fs.tree[child].is_directory => curr = child,
+                            Some(_) => {
+                                anomalies.push(Anomaly::NotADirectory {
+                                    line: line_number,
+                                    name: target.to_string(),
+                                });
+                            }
+                            None => {
+                                anomalies.push(Anomaly::UnknownDirectory {
+                                    line: line_number,
+                                    name: target.to_string(),
+                                });
+                                curr = fs.find_or_create_child(curr, target.to_string(), true);
+                            }
+                        }
+                    }
+                }
+                [size_or_dir, name] if !size_or_dir.starts_with('$') => {
+                    let Some(names) = listing.as_mut() else {
+                        anomalies.push(Anomaly::Malformed {
+                            line: line_number,
+                            text: line.clone(),
+                        });
+                        continue;
+                    };
+                    let size = match *size_or_dir {
+                        "dir" => None,
+                        size => match size.parse::<u32>() {
+                            Ok(size) => Some(size),
+                            Err(_) => {
+                                anomalies.push(Anomaly::Malformed {
+                                    line: line_number,
+                                    text: line.clone(),
+                                });
+                                continue;
+                            }
+                        },
+                    };
+
+                    if !names.insert(name.to_string()) {
+                        anomalies.push(Anomaly::DuplicateEntry {
+                            line: line_number,
+                            name: name.to_string(),
+                        });
+                        continue;
+                    }
+                    match fs.child(curr, name) {
+                        // already seen in an earlier listing, so only check it still agrees
+                        Some(child) => {
+                            let file = &fs.tree[child];
+                            let agrees = match size {
+                                None => file.is_directory,
+                                Some(size) => !file.is_directory && file.size == size,
+                            };
+                            if !agrees {
+                                anomalies.push(Anomaly::ConflictingEntry {
+                                    line: line_number,
+                                    name: name.to_string(),
+                                });
+                            }
+                        }
+                        None => {
+                            let child =
+                                fs.find_or_create_child(curr, name.to_string(), size.is_none());
+                            if let Some(size) = size {
+                                fs.add_size(child, size);
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    listing = None;
+                    anomalies.push(Anomaly::Malformed {
+                        line: line_number,
+                        text: line.clone(),
+                    });
+                }
+            }
+        }
+
+        return (fs, anomalies);
+    }
+
     fn find_or_create_child(
-        strong_self: &Rc<RefCell<File>>,
+        &mut self,
+        parent: Id<File>,
         name: String,
         is_directory: bool,
-    ) -> Rc<RefCell<File>> {
-        for child in &strong_self.borrow().children {
-            if child.borrow().name == name {
-                return Rc::clone(child);
-            }
+    ) -> Id<File> {
+        if let Some(child) = self.child(parent, &name) {
+            return child;
         }
-        let new_file = Rc::new(RefCell::new(File {
-            parent: Rc::downgrade(&strong_self),
-            children: vec![],
-            name,
-            size: 0,
-            is_directory,
-        }));
-        strong_self.borrow_mut().children.push(Rc::clone(&new_file));
-        return new_file;
-    }
-
-    fn add_size(&mut self, size: u32) {
-        self.size += size;
-        let parent = self.parent.upgrade();
-        if parent.is_none() {
-            return;
+        return self.tree.add_child(
+            parent,
+            File {
+                name,
+                size: 0,
+                is_directory,
+            },
+        );
+    }
+
+    fn add_size(&mut self, id: Id<File>, size: u32) {
+        let ancestors: Vec<Id<File>> = self.tree.ancestors(id).collect();
+        for ancestor in ancestors {
+            self.tree[ancestor].size += size;
         }
-        parent.unwrap().borrow_mut().add_size(size);
     }
 
-    fn get_small_dirs_size(&self) -> u32 {
-        let sub = self.children.iter().fold(0, |acc, val| {
-            let file = val.borrow();
-            if !file.is_directory {
-                return acc;
-            }
+    pub(crate) fn root(&self) -> Id<File> {
+        return self.tree.root();
+    }
+
+    pub(crate) fn child(&self, parent: Id<File>, name: &str) -> Option<Id<File>> {
+        return self
+            .tree
+            .children(parent)
+            .iter()
+            .copied()
+            .find(|child| self.tree[*child].name == name);
+    }
+
+    /// Finds the file or directory at an absolute path such as `/a/e`.
+    #[allow(dead_code)]
+    pub(crate) fn lookup(&self, path: &str) -> Option<Id<File>> {
+        let relative = path.strip_prefix('/')?;
+        return relative
+            .split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(self.root(), |curr, name| self.child(curr, name));
+    }
+
+    /// The absolute path of a file or directory.
+    #[allow(dead_code)]
+    pub(crate) fn path(&self, id: Id<File>) -> String {
+        let mut names: Vec<&str> = self
+            .tree
+            .ancestors(id)
+            .take_while(|ancestor| *ancestor != self.root())
+            .map(|ancestor| self.tree[ancestor].name.as_str())
+            .collect();
+        names.reverse();
+        return format!("/{}", names.join("/"));
+    }
+
+    /// Every directory, parents before their children.
+    pub(crate) fn directories(&self) -> impl Iterator<Item = Id<File>> + '_ {
+        return self
+            .tree
+            .descendants(self.root())
+            .filter(|id| self.tree[*id].is_directory);
+    }
+
+    /// The total size of every directory with its path, like `du`.
+    #[allow(dead_code)]
+    pub(crate) fn du(&self) -> Vec<(String, u32)> {
+        return self
+            .directories()
+            .map(|id| (self.path(id), self.tree[id].size))
+            .collect();
+    }
 
-            return acc + file.get_small_dirs_size();
+    /// The directories whose total size matches `predicate`.
+    pub(crate) fn find_directories(&self, predicate: impl Fn(u32) -> bool) -> Vec<Id<File>> {
+        return self
+            .directories()
+            .filter(|id| predicate(self.tree[*id].size))
+            .collect();
+    }
+
+    /// The `n` biggest directories, biggest first.
+    #[allow(dead_code)]
+    pub(crate) fn largest(&self, n: usize) -> Vec<(String, u32)> {
+        let mut sizes = self.du();
+        sizes.sort_by(|(a_path, a_size), (b_path, b_size)| {
+            return b_size.cmp(a_size).then_with(|| a_path.cmp(b_path));
         });
+        sizes.truncate(n);
+        return sizes;
+    }
+
+    /// Draws the filesystem the way the puzzle does, like `tree`.
+    #[allow(dead_code)]
+    pub(crate) fn render(&self) -> String {
+        let mut lines = vec![];
+        self.render_into(self.root(), 0, &mut lines);
+        return lines.join("\n");
+    }
 
-        if self.size < 100_000 {
-            return self.size + sub;
+    fn render_into(&self, id: Id<File>, depth: usize, lines: &mut Vec<String>) {
+        let file = &self.tree[id];
+        let kind = if file.is_directory {
+            "dir".to_string()
         } else {
-            return sub;
+            format!("file, size={}", file.size)
+        };
+        lines.push(format!("{}- {} ({})", "  ".repeat(depth), file.name, kind));
+        for child in self.tree.children(id) {
+            self.render_into(*child, depth + 1, lines);
+        }
+    }
+
+    pub(crate) fn used(&self) -> u32 {
+        return self.tree[self.root()].size;
+    }
+}
+
+// the JSON form of a file, where directories list their children
+struct Node {
+    name: String,
+    size: u32,
+    is_directory: bool,
+    children: Vec<Node>,
+}
+
+// any JSON value, read before picking out the fields a node needs
+enum Json {
+    Null,
+    Bool(bool),
+    // kept as written, as only whole numbers are needed
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+fn escape_json(str: &str) -> String {
+    let mut escaped = String::with_capacity(str.len());
+    for char in str.chars() {
+        match char {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            char if char.is_control() => escaped.push_str(&format!("\\u{:04x}", char as u32)),
+            char => escaped.push(char),
         }
     }
+    return escaped;
+}
 
-    fn get_smallest_dir_bigger_than(&self, target: u32) -> u32 {
-        let min_contained = self
-            .children
+// the code unit of a `\uXXXX` escape, with `input` just after the `u`
+fn hex_escape(input: &str) -> Result<(u32, &str), Failure<'_>> {
+    return input
+        .get(..4)
+        .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+        .map(|code| (code, &input[4..]))
+        .ok_or(Failure::new(input, "4 hex digits"));
+}
+
+fn json_string(input: &str) -> PResult<'_, String> {
+    let mut rest = input
+        .strip_prefix('"')
+        .ok_or(Failure::new(input, "string"))?;
+    let mut value = String::new();
+    loop {
+        let mut chars = rest.chars();
+        let char = chars.next().ok_or(Failure::new(rest, "closing quote"))?;
+        let after = chars.as_str();
+        match char {
+            '"' => return Ok((value, after)),
+            '\\' => {
+                let mut chars = after.chars();
+                let escape = chars.next();
+                rest = chars.as_str();
+                match escape {
+                    Some('"') => value.push('"'),
+                    Some('\\') => value.push('\\'),
+                    Some('/') => value.push('/'),
+                    Some('b') => value.push('\u{8}'),
+                    Some('f') => value.push('\u{c}'),
+                    Some('n') => value.push('\n'),
+                    Some('r') => value.push('\r'),
+                    Some('t') => value.push('\t'),
+                    Some('u') => {
+                        let (code, after_code) = hex_escape(rest)?;
+                        rest = after_code;
+                        // characters outside the basic plane are written as a surrogate pair
+                        let code = if (0xD800..0xDC00).contains(&code) {
+                            let (low, after_low) = rest
+                                .strip_prefix("\\u")
+                                .ok_or(Failure::new(rest, "low surrogate"))
+                                .and_then(hex_escape)?;
+                            if !(0xDC00..0xE000).contains(&low) {
+                                return Err(Failure::new(rest, "low surrogate"));
+                            }
+                            rest = after_low;
+                            0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00)
+                        } else {
+                            code
+                        };
+                        value.push(char::from_u32(code).ok_or(Failure::new(rest, "character"))?);
+                    }
+                    _ => return Err(Failure::new(after, "escape sequence")),
+                }
+            }
+            char if char.is_control() => return Err(Failure::new(rest, "closing quote")),
+            char => {
+                value.push(char);
+                rest = after;
+            }
+        }
+    }
+}
+
+fn json_number(input: &str) -> PResult<'_, String> {
+    let (number, rest) =
+        take_while(|char| char.is_ascii_digit() || "+-.eE".contains(char)).parse(input)?;
+    if number.is_empty() {
+        return Err(Failure::new(input, "number"));
+    }
+    return Ok((number, rest));
+}
+
+fn json_member(input: &str) -> PResult<'_, (String, Json)> {
+    return delimited(whitespace(), from_fn(json_string), whitespace())
+        .skip(literal(":"))
+        .then(json_value)
+        .parse(input);
+}
+
+fn json_value(input: &str) -> PResult<'_, Json> {
+    let array = delimited(
+        literal("[").skip(whitespace()),
+        separated(json_value, literal(",")),
+        literal("]"),
+    );
+    let object = delimited(
+        literal("{").skip(whitespace()),
+        separated(json_member, literal(",")),
+        literal("}"),
+    );
+    let value = object
+        .map(Json::Object)
+        .or(array.map(Json::Array))
+        .or(from_fn(json_string).map(Json::String))
+        .or(from_fn(json_number).map(Json::Number))
+        .or(literal("true").map(|_| Json::Bool(true)))
+        .or(literal("false").map(|_| Json::Bool(false)))
+        .or(literal("null").map(|_| Json::Null))
+        .expect("a JSON value");
+    return delimited(whitespace(), value, whitespace()).parse(input);
+}
+
+// whether a file can be called this in a transcript, which splits lines on whitespace and treats
+// `/` and `..` as moves
+fn transcript_name(name: &str) -> bool {
+    return !matches!(name, "" | "." | "..")
+        && !name
+            .chars()
+            .any(|char| char == '/' || char.is_whitespace() || char.is_control());
+}
+
+// picks a node's fields out of a JSON object, in any order and ignoring any others
+fn json_node(json: Json) -> Result<Node, String> {
+    let Json::Object(members) = json else {
+        return Err("Expected each file to be a JSON object".to_string());
+    };
+    let (mut name, mut size, mut is_directory, mut children) = (None, None, None, vec![]);
+    for (key, value) in members {
+        match (key.as_str(), value) {
+            ("name", Json::String(value)) => name = Some(value),
+            ("size", Json::Number(value)) => {
+                size = Some(
+                    value
+                        .parse::<u32>()
+                        .map_err(|_| format!("Expected a whole number size, found {}", value))?,
+                );
+            }
+            ("is_directory", Json::Bool(value)) => is_directory = Some(value),
+            ("children", Json::Array(values)) => {
+                children = values
+                    .into_iter()
+                    .map(json_node)
+                    .collect::<Result<_, _>>()?;
+            }
+            ("name" | "size" | "is_directory" | "children", _) => {
+                return Err(format!("{:?} has the wrong type", key));
+            }
+            _ => (),
+        }
+    }
+
+    let name = name.ok_or("Expected every file to have a \"name\"")?;
+    let is_directory = is_directory.ok_or(format!("Expected {} to have \"is_directory\"", name))?;
+    // directory sizes are worked out from their contents, so only files need one
+    let size = match (size, is_directory) {
+        (Some(size), _) => size,
+        (None, true) => 0,
+        (None, false) => return Err(format!("Expected file {} to have a \"size\"", name)),
+    };
+    return Ok(Node {
+        name,
+        size,
+        is_directory,
+        children,
+    });
+}
+
+#[allow(dead_code)]
+impl FileSystem {
+    /// Writes the tree as nested JSON objects with `name`, `size`, `is_directory` and `children`.
+    pub(crate) fn to_json(&self) -> String {
+        return self.json_of(self.root());
+    }
+
+    fn json_of(&self, id: Id<File>) -> String {
+        let file = &self.tree[id];
+        let children: Vec<String> = self
+            .tree
+            .children(id)
             .iter()
-            .map(|val| {
-                let file = val.borrow();
-                if !file.is_directory {
-                    return u32::MAX;
+            .map(|child| self.json_of(*child))
+            .collect();
+        return format!(
+            "{{\"name\":\"{}\",\"size\":{},\"is_directory\":{},\"children\":[{}]}}",
+            escape_json(&file.name),
+            file.size,
+            file.is_directory,
+            children.join(",")
+        );
+    }
+
+    /// Reads a tree in the form written by [`FileSystem::to_json`], with its keys in any order.
+    /// Directory sizes are worked out again from the files inside them. Names a transcript
+    /// couldn't hold, like ones with spaces or `/` in them, are rejected.
+    pub(crate) fn from_json(json: &str) -> Result<Self, String> {
+        let json = parse_complete(from_fn(json_value), json).map_err(|error| error.to_string())?;
+        let root = json_node(json)?;
+        if !root.is_directory || root.name != "/" {
+            return Err(format!(
+                "Expected the root directory \"/\", found {:?}",
+                root.name
+            ));
+        }
+
+        let mut fs = Self::new();
+        let mut pending: Vec<(Id<File>, Node)> = vec![(fs.root(), root)];
+        while let Some((id, node)) = pending.pop() {
+            for child in node.children {
+                if !transcript_name(&child.name) {
+                    return Err(format!(
+                        "{:?} in {} isn't a valid name",
+                        child.name,
+                        fs.path(id)
+                    ));
+                }
+                if !node.is_directory {
+                    return Err(format!("File {} can't contain {}", fs.path(id), child.name));
+                }
+                if fs.child(id, &child.name).is_some() {
+                    return Err(format!("{} is listed twice in {}", child.name, fs.path(id)));
                 }
+                let child_id = fs.find_or_create_child(id, child.name.clone(), child.is_directory);
+                if !child.is_directory {
+                    fs.add_size(child_id, child.size);
+                }
+                pending.push((child_id, child));
+            }
+        }
+        return Ok(fs);
+    }
 
-                return file.get_smallest_dir_bigger_than(target);
-            })
-            .min()
-            .unwrap_or(u32::MAX);
+    /// Writes a terminal transcript that lists every directory, which replays to this tree.
+    pub(crate) fn to_transcript(&self) -> Vec<String> {
+        let mut lines = vec!["$ cd /".to_string()];
+        self.transcript_of(self.root(), &mut lines);
+        return lines;
+    }
 
-        if self.size >= target && self.size < min_contained {
-            return self.size;
-        } else {
-            return min_contained;
+    fn transcript_of(&self, id: Id<File>, lines: &mut Vec<String>) {
+        lines.push("$ ls".to_string());
+        for child in self.tree.children(id) {
+            let file = &self.tree[*child];
+            if file.is_directory {
+                lines.push(format!("dir {}", file.name));
+            } else {
+                lines.push(format!("{} {}", file.size, file.name));
+            }
+        }
+        for child in self.tree.children(id) {
+            if self.tree[*child].is_directory {
+                lines.push(format!("$ cd {}", self.tree[*child].name));
+                self.transcript_of(*child, lines);
+                lines.push("$ cd ..".to_string());
+            }
         }
     }
 }
 
+impl std::ops::Index<Id<File>> for FileSystem {
+    type Output = File;
+
+    fn index(&self, id: Id<File>) -> &File {
+        return &self.tree[id];
+    }
+}
+
 pub(crate) struct Day07 {
-    root: Rc<RefCell<File>>,
+    fs: FileSystem,
+    capacity: u32,
+    required: u32,
 }
 
 impl Day07 {
     pub(crate) fn new() -> Self {
-        let lines = crate::utils::read_day_lines(7);
+        return Self::with_disk(70_000_000, 30_000_000);
+    }
 
-        let root = Rc::new(RefCell::new(File {
-            parent: Weak::new(),
-            children: vec![],
-            name: "/".to_string(),
-            size: 0,
-            is_directory: true,
-        }));
-        let curr = &mut Rc::clone(&root);
-
-        for line in &lines {
-            if line.starts_with('$') {
-                let parts: Vec<&str> = line.split_ascii_whitespace().collect();
-                if parts[1] == "cd" {
-                    if parts[2] == ".." {
-                        let tmp = Rc::clone(&(curr.borrow().parent.upgrade().unwrap()));
-                        *curr = tmp;
-                    } else if parts[2] != "/" {
-                        *curr = File::find_or_create_child(curr, parts[2].to_string(), true);
-                    }
-                }
-            } else {
-                let parts: Vec<&str> = line.split_ascii_whitespace().collect();
-                let is_directory = parts[0] == "dir";
-                let child = File::find_or_create_child(curr, parts[1].to_string(), is_directory);
-                if !is_directory {
-                    child
-                        .borrow_mut()
-                        .add_size(parts[0].parse::<u32>().unwrap());
-                }
-            }
-        }
+    /// Reads the transcript for a disk of `capacity` that needs `required` free for the update.
+    pub(crate) fn with_disk(capacity: u32, required: u32) -> Self {
+        let lines = crate::utils::read_day_lines(7);
+        return Self {
+            fs: FileSystem::from_transcript(&lines),
+            capacity,
+            required,
+        };
+    }
 
-        return Self { root };
+    /// The size of the smallest directory that frees up enough space, if any does.
+    pub(crate) fn smallest_to_free(&self) -> Option<u32> {
+        let free = self.capacity.saturating_sub(self.fs.used());
+        let to_free = self.required.saturating_sub(free);
+        return self
+            .fs
+            .find_directories(|size| size >= to_free)
+            .iter()
+            .map(|id| self.fs[*id].size)
+            .min();
     }
 }
 
 impl super::Day for Day07 {
     fn part_1(&mut self) -> String {
-        return self.root.borrow().get_small_dirs_size().to_string();
+        return self
+            .fs
+            .find_directories(|size| size < 100_000)
+            .iter()
+            .map(|id| self.fs[*id].size)
+            .sum::<u32>()
+            .to_string();
     }
 
     fn part_2(&mut self) -> String {
-        let to_free = 30_000_000 - (70_000_000 - self.root.borrow().size);
         return self
-            .root
-            .borrow()
-            .get_smallest_dir_bigger_than(to_free)
+            .smallest_to_free()
+            .expect("No directory is big enough to free up the space")
             .to_string();
     }
 }
@@ -158,4 +680,117 @@ mod test {
         let mut day = Day07::new();
         assert_eq!(day.part_2(), "24933642");
     }
+
+    #[test]
+    fn queries() {
+        let day = Day07::new();
+        let fs = &day.fs;
+        let e = fs.lookup("/a/e").unwrap();
+        assert_eq!(fs[e].size, 584);
+        assert_eq!(fs.path(e), "/a/e");
+        assert_eq!(fs.lookup("/a/x"), None);
+        assert_eq!(
+            fs.largest(2),
+            vec![("/".to_string(), 48381165), ("/d".to_string(), 24933642)]
+        );
+        assert!(fs.render().starts_with(
+            "- / (dir)\n  - a (dir)\n    - e (dir)\n      - i (file, size=584)\n    - f (file, size=29116)"
+        ));
+
+        let day = Day07::with_disk(50_000_000, 1_700_000);
+        assert_eq!(day.smallest_to_free(), Some(94853));
+    }
+
+    #[test]
+    fn round_trip() {
+        let day = Day07::new();
+        let json = day.fs.to_json();
+        assert!(json.starts_with(
+            "{\"name\":\"/\",\"size\":48381165,\"is_directory\":true,\"children\":[{\"name\":\"a\""
+        ));
+        let imported = FileSystem::from_json(&json).unwrap();
+        assert_eq!(imported.render(), day.fs.render());
+        assert_eq!(imported.to_json(), json);
+
+        let replayed = FileSystem::from_transcript(&day.fs.to_transcript());
+        assert_eq!(replayed.render(), day.fs.render());
+
+        let error =
+            FileSystem::from_json("{\"name\": \"/\", \"size\": 0, \"is_directory\": maybe}");
+        assert_eq!(
+            error.err(),
+            Some("line 1, column 42: expected a JSON value, found \"maybe}\"".to_string())
+        );
+    }
+
+    #[test]
+    fn standard_json() {
+        let json = r#"{
+            "children": [
+                {"size": 5, "name": "caf\u00e9\ud83d\ude00", "is_directory": false, "mode": null},
+                {"is_directory": true, "name": "empty", "children": [ ]}
+            ],
+            "is_directory": true,
+            "name": "/"
+        }"#;
+        let fs = FileSystem::from_json(json).unwrap();
+        let file = fs.child(fs.root(), "café😀").unwrap();
+        assert_eq!(fs[file].size, 5);
+        assert_eq!(fs.used(), 5);
+
+        let written = fs.to_json();
+        assert!(written.contains(r#""name":"café😀""#));
+        assert_eq!(FileSystem::from_json(&written).unwrap().to_json(), written);
+        let replayed = FileSystem::from_transcript(&fs.to_transcript());
+        assert_eq!(replayed.to_json(), written);
+
+        // control characters are escaped, so anything written reads back the same
+        assert_eq!(escape_json("a\n\"b\u{1}"), r#"a\n\"b\u0001"#);
+        for name in ["caf\\u00e9\\n", "a b", "a/b", "..", ""] {
+            let json = format!(
+                r#"{{"name": "/", "is_directory": true, "children": [{{"name": "{}", "size": 1, "is_directory": false}}]}}"#,
+                name
+            );
+            assert!(
+                FileSystem::from_json(&json).is_err(),
+                "{:?} should be rejected",
+                name
+            );
+        }
+
+        assert_eq!(
+            FileSystem::from_json(r#"{"name": "/", "is_directory": true, "children": [{"name": "a", "is_directory": false}]}"#).err(),
+            Some("Expected file a to have a \"size\"".to_string())
+        );
+    }
+
+    #[test]
+    fn anomalies() {
+        let transcript: Vec<String> = [
+            "$ cd /", "$ ls", "dir a", "10 b", "10 b", "$ ls", "dir a", "10 b", "$ cd ..",
+            "$ cd x", "$ cd /", "$ ls", "20 b", "$ cd b", "oops",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        let (fs, anomalies) = FileSystem::replay(&transcript);
+        assert_eq!(fs.used(), 10);
+        assert_eq!(
+            anomalies
+                .iter()
+                .map(|anomaly| anomaly.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "line 5: b is listed twice",
+                "line 9: cd .. from the root directory",
+                "line 10: cd into x, which hasn't been listed",
+                "line 13: b doesn't match its earlier listing",
+                "line 14: cd into b, which is a file",
+                "line 15: unexpected \"oops\"",
+            ]
+        );
+
+        let day = Day07::new();
+        assert!(FileSystem::replay(&day.fs.to_transcript()).1.is_empty());
+    }
 }
@@ -1,8 +1,90 @@
-use std::{sync::Arc, thread};
+type Forest = Vec<Vec<u8>>;
+
+// for each tree in a line, how far it can see back towards the start of the line. A stack keeps
+// the trees that are still tall enough to block the view of later ones, so each is popped once.
+fn distances_back(heights: impl Iterator<Item = u8>) -> Vec<usize> {
+    let mut blockers: Vec<(usize, u8)> = vec![];
+    return heights
+        .enumerate()
+        .map(|(i, height)| {
+            while blockers
+                .last()
+                .is_some_and(|(_, blocker)| *blocker < height)
+            {
+                blockers.pop();
+            }
+            let distance = blockers.last().map_or(i, |(blocker, _)| i - blocker);
+            blockers.push((i, height));
+            return distance;
+        })
+        .collect();
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct BestTree {
+    pub(crate) x: usize,
+    pub(crate) y: usize,
+    pub(crate) score: usize,
+    // up, down, left and right, like `Day08::viewing_distances`
+    pub(crate) distances: [usize; 4],
+}
+
+fn bools_to_levels(values: &[Vec<bool>]) -> Vec<Vec<usize>> {
+    return values
+        .iter()
+        .map(|row| row.iter().map(|value| *value as usize).collect())
+        .collect();
+}
 
-use rayon::prelude::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+/// Writes a matrix as a plain PGM image, scaled so the largest value is white. Values are kept
+/// as they are when they fit in 255 grey levels.
+fn pgm(values: &[Vec<usize>]) -> String {
+    let max = values.iter().flatten().copied().max().unwrap_or(0).max(1);
+    let white = max.min(255);
+    let width = values.first().map_or(0, Vec::len);
+    let mut lines = vec![format!("P2\n{} {}\n{}", width, values.len(), white)];
+    lines.extend(values.iter().map(|row| {
+        return row
+            .iter()
+            .map(|value| (value * white / max).to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+    }));
+    return lines.join("\n") + "\n";
+}
+
+/// Writes a matrix as a plain PPM image, shading from blue at zero to red at the largest value.
+fn heatmap_ppm(values: &[Vec<usize>]) -> String {
+    let max = values.iter().flatten().copied().max().unwrap_or(0).max(1);
+    let width = values.first().map_or(0, Vec::len);
+    let mut lines = vec![format!("P3\n{} {}\n255", width, values.len())];
+    lines.extend(values.iter().map(|row| {
+        return row
+            .iter()
+            .map(|value| {
+                let red = value * 255 / max;
+                return format!("{} 0 {}", red, 255 - red);
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+    }));
+    return lines.join("\n") + "\n";
+}
 
-type Forest = Arc<Vec<Vec<u8>>>;
+fn csv(values: &[Vec<usize>]) -> String {
+    return values
+        .iter()
+        .map(|row| {
+            return row
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(",");
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        + "\n";
+}
 
 pub(crate) struct Day08 {
     forest: Forest,
@@ -10,81 +92,13 @@ pub(crate) struct Day08 {
 
 impl Day08 {
     pub(crate) fn new() -> Self {
-        let vals = Arc::new(crate::utils::read_day_digit_lines(8));
+        let vals = crate::utils::read_day_digit_lines(8);
 
         return Self { forest: vals };
     }
 
-    fn visible_from(&self, x: usize, y: usize) -> usize {
-        let height = self.forest[y][x];
-        let column_len = self.forest.len();
-        let row_len = self.forest[0].len();
-
-        let up_forest = self.forest.clone();
-        let up_handle = thread::spawn(move || {
-            let mut seen = 0;
-            let mut curr_y = y;
-            while curr_y > 0 {
-                seen += 1;
-                curr_y -= 1;
-                if up_forest[curr_y][x] >= height {
-                    break;
-                }
-            }
-            seen
-        });
-
-        let down_forest = self.forest.clone();
-        let down_handle = thread::spawn(move || {
-            let mut seen = 0;
-            let mut curr_y = y + 1;
-            while curr_y < column_len {
-                seen += 1;
-                if down_forest[curr_y][x] >= height {
-                    break;
-                }
-                curr_y += 1;
-            }
-            seen
-        });
-
-        let left_forest = self.forest.clone();
-        let left_handle = thread::spawn(move || {
-            let mut seen = 0;
-            let mut curr_x = x;
-            while curr_x > 0 {
-                seen += 1;
-                curr_x -= 1;
-                if left_forest[y][curr_x] >= height {
-                    break;
-                }
-            }
-            seen
-        });
-
-        let right_forest = self.forest.clone();
-        let right_handle = thread::spawn(move || {
-            let mut seen = 0;
-            let mut curr_x = x + 1;
-            while curr_x < row_len {
-                seen += 1;
-                if right_forest[y][curr_x] >= height {
-                    break;
-                }
-                curr_x += 1;
-            }
-            seen
-        });
-
-        up_handle.join().unwrap()
-            * down_handle.join().unwrap()
-            * left_handle.join().unwrap()
-            * right_handle.join().unwrap()
-    }
-}
-
-impl super::Day for Day08 {
-    fn part_1(&mut self) -> String {
+    /// Whether each tree can be seen from outside the forest.
+    pub(crate) fn visibility(&self) -> Vec<Vec<bool>> {
         let rows_len = self.forest.len();
         let mut visible: Vec<Vec<bool>> = self
             .forest
@@ -177,30 +191,115 @@ impl super::Day for Day08 {
             }
         }
 
-        let sum: u32 = visible
+        return visible;
+    }
+
+    /// How many trees each tree can see up, down, left and right, in that order.
+    pub(crate) fn viewing_distances(&self) -> Vec<Vec<[usize; 4]>> {
+        let rows_len = self.forest.len();
+        let columns_len = self.forest.first().map_or(0, Vec::len);
+        let mut distances = vec![vec![[0; 4]; columns_len]; rows_len];
+
+        for (y, row) in self.forest.iter().enumerate() {
+            let left = distances_back(row.iter().copied());
+            let right = distances_back(row.iter().rev().copied());
+            for x in 0..columns_len {
+                distances[y][x][2] = left[x];
+                distances[y][x][3] = right[columns_len - 1 - x];
+            }
+        }
+
+        for x in 0..columns_len {
+            let up = distances_back(self.forest.iter().map(|row| row[x]));
+            let down = distances_back(self.forest.iter().rev().map(|row| row[x]));
+            for y in 0..rows_len {
+                distances[y][x][0] = up[y];
+                distances[y][x][1] = down[rows_len - 1 - y];
+            }
+        }
+
+        return distances;
+    }
+
+    /// The scenic score of every tree, the product of its viewing distances.
+    pub(crate) fn scenic_scores(&self) -> Vec<Vec<usize>> {
+        return self
+            .viewing_distances()
             .iter()
             .map(|row| {
-                row.iter()
-                    .map(|cell| if *cell { 1 } else { 0 })
-                    .sum::<u32>()
+                return row
+                    .iter()
+                    .map(|distances| distances.iter().product())
+                    .collect();
             })
-            .sum();
+            .collect();
+    }
+
+    /// The tree with the highest scenic score, taking the first in reading order on a tie.
+    #[allow(dead_code)]
+    pub(crate) fn best_tree(&self) -> Option<BestTree> {
+        let mut best: Option<BestTree> = None;
+        for (y, row) in self.viewing_distances().iter().enumerate() {
+            for (x, distances) in row.iter().enumerate() {
+                let score = distances.iter().product();
+                if best.as_ref().is_none_or(|best| score > best.score) {
+                    best = Some(BestTree {
+                        x,
+                        y,
+                        score,
+                        distances: *distances,
+                    });
+                }
+            }
+        }
+        return best;
+    }
+
+    /// The visibility mask as a black and white PGM image, with visible trees in white.
+    #[allow(dead_code)]
+    pub(crate) fn visibility_pgm(&self) -> String {
+        return pgm(&bools_to_levels(&self.visibility()));
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn visibility_csv(&self) -> String {
+        return csv(&bools_to_levels(&self.visibility()));
+    }
+
+    /// The scenic scores as a greyscale PGM image, brightest for the best tree.
+    #[allow(dead_code)]
+    pub(crate) fn scenic_scores_pgm(&self) -> String {
+        return pgm(&self.scenic_scores());
+    }
 
-        return sum.to_string();
+    /// The scenic scores as a PPM heatmap, from blue for the lowest to red for the highest.
+    #[allow(dead_code)]
+    pub(crate) fn scenic_scores_ppm(&self) -> String {
+        return heatmap_ppm(&self.scenic_scores());
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn scenic_scores_csv(&self) -> String {
+        return csv(&self.scenic_scores());
+    }
+}
+
+impl super::Day for Day08 {
+    fn part_1(&mut self) -> String {
+        return self
+            .visibility()
+            .iter()
+            .flatten()
+            .filter(|visible| **visible)
+            .count()
+            .to_string();
     }
 
     fn part_2(&mut self) -> String {
         return self
-            .forest
-            .par_iter()
-            .enumerate()
-            .map(|(y, row)| {
-                row.par_iter()
-                    .enumerate()
-                    .map(|(x, _)| self.visible_from(x, y))
-                    .max()
-                    .unwrap()
-            })
+            .scenic_scores()
+            .iter()
+            .flatten()
             .max()
             .unwrap()
             .to_string();
@@ -223,4 +322,44 @@ mod test {
         let mut day = Day08::new();
         assert_eq!(day.part_2(), "8");
     }
+
+    #[test]
+    fn scenic_scores() {
+        let day = Day08::new();
+        let scores = day.scenic_scores();
+        assert_eq!(scores[1][2], 4);
+        assert_eq!(scores[3][2], 8);
+        assert_eq!(scores[0], vec![0; 5]);
+        assert_eq!(day.viewing_distances()[3][2], [2, 1, 2, 2]);
+    }
+
+    #[test]
+    fn exports() {
+        let day = Day08::new();
+        assert_eq!(
+            day.best_tree(),
+            Some(BestTree {
+                x: 2,
+                y: 3,
+                score: 8,
+                distances: [2, 1, 2, 2]
+            })
+        );
+        assert!(day
+            .visibility_pgm()
+            .starts_with("P2\n5 5\n1\n1 1 1 1 1\n1 1 1 0 1\n"));
+        assert!(day
+            .scenic_scores_csv()
+            .starts_with("0,0,0,0,0\n0,1,4,1,0\n"));
+        assert!(day.scenic_scores_ppm().starts_with("P3\n5 5\n255\n0 0 255"));
+        assert!(day
+            .scenic_scores_pgm()
+            .starts_with("P2\n5 5\n8\n0 0 0 0 0\n0 1 4 1 0\n"));
+
+        // the format can't hold grey levels of 65536 or more
+        assert_eq!(
+            pgm(&[vec![0, 100_000, 400_000]]),
+            "P2\n3 1\n255\n0 63 255\n"
+        );
+    }
 }
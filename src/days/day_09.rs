@@ -1,5 +1,6 @@
-use core::panic;
+use std::collections::HashSet;
 
+type Coord = (isize, isize);
 type Instruction = (char, usize);
 
 pub(crate) struct Day09 {
@@ -8,8 +9,7 @@ pub(crate) struct Day09 {
 
 impl Day09 {
     pub(crate) fn new() -> Self {
-        let instructions = crate::utils::read_day_lines(9)
-            .iter()
+        let instructions = crate::utils::stream_day_lines(9)
             .map(|line| {
                 let mut parts = line.split_ascii_whitespace();
                 (
@@ -22,106 +22,102 @@ impl Day09 {
     }
 }
 
-fn update_tail_position(head_x: usize, head_y: usize, tail_x: &mut usize, tail_y: &mut usize) {
-    if head_x - 1 > *tail_x {
-        *tail_x += 1;
-        if *tail_y < head_y {
-            *tail_y += 1;
-        } else if *tail_y > head_y {
-            *tail_y -= 1;
-        }
-    } else if head_x + 1 < *tail_x {
-        *tail_x -= 1;
-        if *tail_y < head_y {
-            *tail_y += 1;
-        } else if *tail_y > head_y {
-            *tail_y -= 1;
-        }
-    } else if head_y - 1 > *tail_y {
-        *tail_y += 1;
-        if *tail_x < head_x {
-            *tail_x += 1;
-        } else if *tail_x > head_x {
-            *tail_x -= 1;
-        }
-    } else if head_y + 1 < *tail_y {
-        *tail_y -= 1;
-        if *tail_x < head_x {
-            *tail_x += 1;
-        } else if *tail_x > head_x {
-            *tail_x -= 1;
-        }
+// where a knot moves to once the knot ahead of it has moved
+fn follow(head: Coord, tail: Coord) -> Coord {
+    let (dx, dy) = (head.0 - tail.0, head.1 - tail.1);
+    if dx.abs() <= 1 && dy.abs() <= 1 {
+        return tail;
     }
+    return (tail.0 + dx.signum(), tail.1 + dy.signum());
 }
 
-impl super::Day for Day09 {
-    fn part_1(&mut self) -> String {
-        let mut visited = vec![vec![false; 4096]; 4096];
-        let mut head_x = 2048;
-        let mut head_y = 2048;
-        let mut tail_x = 2048;
-        let mut tail_y = 2048;
-
-        for (dir, count) in self.instructions.iter() {
-            for _ in 0..*count {
-                match *dir {
-                    'R' => head_x += 1,
-                    'L' => head_x -= 1,
-                    'U' => head_y += 1,
-                    'D' => head_y -= 1,
-                    _ => panic!("Unrecognized direction: {}", *dir),
-                }
+fn step(dir: char) -> Coord {
+    match dir {
+        'R' => return (1, 0),
+        'L' => return (-1, 0),
+        'U' => return (0, 1),
+        'D' => return (0, -1),
+        _ => panic!("Unrecognized direction: {}", dir),
+    }
+}
 
-                update_tail_position(head_x, head_y, &mut tail_x, &mut tail_y);
+/// Draws a rope the way the puzzle does, with `H` for the head, `T` for the tail and the other
+/// knots numbered, between the `min` and `max` corners. Knots nearer the head cover later ones.
+#[allow(dead_code)]
+pub(crate) fn render_rope(rope: &[Coord], min: Coord, max: Coord) -> String {
+    let label = |knot: usize| match knot {
+        0 => return 'H',
+        _ if knot == rope.len() - 1 => return 'T',
+        _ => return char::from_digit(knot as u32, 10).unwrap_or('#'),
+    };
+    return (min.1..=max.1)
+        .rev()
+        .map(|y| {
+            return (min.0..=max.0)
+                .map(|x| match rope.iter().position(|knot| *knot == (x, y)) {
+                    Some(knot) => return label(knot),
+                    None if (x, y) == (0, 0) => return 's',
+                    None => return '.',
+                })
+                .collect::<String>();
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+}
+
+impl Day09 {
+    /// The positions of every knot after each single step of the head, starting at the origin.
+    pub(crate) fn states(&self, knots: usize) -> impl Iterator<Item = Vec<Coord>> + '_ {
+        assert!(knots > 0, "A rope needs at least one knot");
+        let mut rope: Vec<Coord> = vec![(0, 0); knots];
+        return self
+            .instructions
+            .iter()
+            .flat_map(|(dir, count)| std::iter::repeat_n(step(*dir), *count))
+            .map(move |(dx, dy)| {
+                rope[0] = (rope[0].0 + dx, rope[0].1 + dy);
+                for i in 1..rope.len() {
+                    rope[i] = follow(rope[i - 1], rope[i]);
+                }
+                return rope.clone();
+            });
+    }
 
-                visited[tail_y][tail_x] = true;
+    /// How many cells each knot of a rope with `knots` knots visits, counting the start.
+    pub(crate) fn visits_per_knot(&self, knots: usize) -> Vec<usize> {
+        let mut visited: Vec<HashSet<Coord>> = vec![HashSet::from([(0, 0)]); knots];
+        for rope in self.states(knots) {
+            for (knot, position) in rope.into_iter().enumerate() {
+                visited[knot].insert(position);
             }
         }
-
-        return visited
-            .iter()
-            .fold(0, |acc, row| {
-                acc + row
-                    .iter()
-                    .fold(0, |acc, val| if *val { acc + 1 } else { acc })
-            })
-            .to_string();
+        return visited.iter().map(HashSet::len).collect();
     }
 
-    fn part_2(&mut self) -> String {
-        let mut visited = vec![vec![false; 4096]; 4096];
-        let mut knots = [(2048_usize, 2048_usize); 10];
-
-        for (dir, count) in self.instructions.iter() {
-            for _ in 0..*count {
-                match *dir {
-                    'R' => knots[0].0 += 1,
-                    'L' => knots[0].0 -= 1,
-                    'U' => knots[0].1 += 1,
-                    'D' => knots[0].1 -= 1,
-                    _ => panic!("Unrecognized direction: {}", *dir),
-                }
+    /// How many cells the last knot of a rope with `knots` knots visits, counting the start.
+    pub(crate) fn tail_visits(&self, knots: usize) -> usize {
+        return self.visits_per_knot(knots)[knots - 1];
+    }
 
-                for i in 1..knots.len() {
-                    let head_x = knots[i - 1].0;
-                    let head_y = knots[i - 1].1;
-                    let tail_x = &mut knots[i].0;
-                    let tail_y = &mut knots[i].1;
-                    update_tail_position(head_x, head_y, tail_x, tail_y);
-                }
+    /// The corners of the smallest area that holds the start and every knot at every step.
+    #[allow(dead_code)]
+    pub(crate) fn bounds(&self, knots: usize) -> (Coord, Coord) {
+        return self
+            .states(knots)
+            .flatten()
+            .fold(((0, 0), (0, 0)), |(min, max), (x, y)| {
+                return ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)));
+            });
+    }
+}
 
-                visited[knots[9].1][knots[9].0] = true;
-            }
-        }
+impl super::Day for Day09 {
+    fn part_1(&mut self) -> String {
+        return self.tail_visits(2).to_string();
+    }
 
-        return visited
-            .iter()
-            .fold(0, |acc, row| {
-                acc + row
-                    .iter()
-                    .fold(0, |acc, val| if *val { acc + 1 } else { acc })
-            })
-            .to_string();
+    fn part_2(&mut self) -> String {
+        return self.tail_visits(10).to_string();
     }
 }
 
@@ -141,4 +137,27 @@ mod test {
         let mut day = Day09::new();
         assert_eq!(day.part_2(), "36");
     }
+
+    #[test]
+    fn rope_lengths() {
+        // wanders well past where the old fixed grid ended
+        let day = Day09 {
+            instructions: vec![('L', 3000), ('D', 5)],
+        };
+        assert_eq!(day.tail_visits(1), 3006);
+        assert_eq!(day.tail_visits(2), 3004);
+    }
+
+    #[test]
+    fn rope_states() {
+        let day = Day09 {
+            instructions: vec![('R', 4), ('U', 2)],
+        };
+        assert_eq!(day.visits_per_knot(3), vec![7, 5, 4]);
+
+        let last = day.states(3).last().unwrap();
+        assert_eq!(last, vec![(4, 2), (4, 1), (3, 1)]);
+        let (min, max) = day.bounds(3);
+        assert_eq!(render_rope(&last, min, max), "....H\n...T1\ns....");
+    }
 }
@@ -1,74 +1,353 @@
-pub(crate) struct Day10 {
-    instructions: Vec<String>,
+use std::{io::BufRead, str::FromStr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Instruction {
+    Noop,
+    Addx(i32),
 }
 
-impl Day10 {
-    pub(crate) fn new() -> Self {
-        let instructions = crate::utils::read_day_lines(10);
-        return Self { instructions };
+impl Instruction {
+    /// How many cycles the instruction takes to finish.
+    pub(crate) fn cycles(&self) -> usize {
+        match self {
+            Instruction::Noop => return 1,
+            Instruction::Addx(_) => return 2,
+        }
     }
 }
 
-impl super::Day for Day10 {
-    fn part_1(&mut self) -> String {
-        let mut cycle = 0;
-        let mut acc = 1;
-        let mut score = 0;
-
-        for instruction in &self.instructions {
-            let mut parts = instruction.split_ascii_whitespace();
-            match parts.next().unwrap() {
-                "noop" => {
-                    cycle += 1;
-                    if (cycle + 20) % 40 == 0 {
-                        score += acc * cycle;
+impl FromStr for Instruction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split_ascii_whitespace().collect();
+        match parts.as_slice() {
+            ["noop"] => return Ok(Instruction::Noop),
+            ["addx", value] => {
+                let value = value
+                    .parse()
+                    .map_err(|_| format!("Expected a number to add, found {:?}", value))?;
+                return Ok(Instruction::Addx(value));
+            }
+            _ => return Err(format!("Unrecognized instruction: {:?}", s)),
+        }
+    }
+}
+
+/// Runs a program a cycle at a time. As an iterator it yields each cycle's number, counting
+/// from 1, along with the value of `X` during that cycle.
+#[derive(Clone, Debug)]
+pub(crate) struct Cpu {
+    program: Vec<Instruction>,
+    // the instruction being run
+    pc: usize,
+    // how many cycles the current instruction has already taken
+    progress: usize,
+    cycle: usize,
+    x: i32,
+}
+
+impl Cpu {
+    pub(crate) fn new(program: Vec<Instruction>) -> Self {
+        return Self {
+            program,
+            pc: 0,
+            progress: 0,
+            cycle: 0,
+            x: 1,
+        };
+    }
+
+    /// The number of cycles finished so far.
+    pub(crate) fn elapsed(&self) -> usize {
+        return self.cycle;
+    }
+
+    pub(crate) fn x(&self) -> i32 {
+        return self.x;
+    }
+
+    /// The instruction that will run during the next cycle, if the program hasn't finished.
+    pub(crate) fn current(&self) -> Option<Instruction> {
+        return self.program.get(self.pc).copied();
+    }
+
+    /// Runs one cycle, returning its number and the value of `X` while it ran.
+    pub(crate) fn tick(&mut self) -> Option<(usize, i32)> {
+        let instruction = self.current()?;
+        let during = (self.cycle + 1, self.x);
+
+        self.cycle += 1;
+        self.progress += 1;
+        if self.progress == instruction.cycles() {
+            if let Instruction::Addx(value) = instruction {
+                self.x += value;
+            }
+            self.pc += 1;
+            self.progress = 0;
+        }
+        return Some(during);
+    }
+
+    /// Whether the cycles run so far have finished an instruction, rather than stopping part way.
+    pub(crate) fn between_instructions(&self) -> bool {
+        return self.progress == 0;
+    }
+}
+
+impl Iterator for Cpu {
+    type Item = (usize, i32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        return self.tick();
+    }
+}
+
+/// Whether the sprite centred on `x` covers the pixel being drawn during `cycle`.
+fn pixel_lit(cycle: usize, x: i32, width: usize) -> bool {
+    let column = ((cycle - 1) % width) as i32;
+    return (column - x).abs() <= 1;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Breakpoint {
+    /// Stops just before the cycle with this number runs.
+    Cycle(usize),
+    /// Stops once `X` changes to this value.
+    X(i32),
+}
+
+/// Steps through a program while drawing the CRT, for working out where a signal goes wrong.
+pub(crate) struct Debugger {
+    cpu: Cpu,
+    width: usize,
+    breakpoints: Vec<Breakpoint>,
+    // how many cycles had run when a cycle breakpoint last stopped the program
+    stopped_at_cycle: Option<usize>,
+    // the pixels drawn so far on the current CRT row, with `#` for lit and `.` for dark
+    row: String,
+}
+
+impl Debugger {
+    pub(crate) fn new(cpu: Cpu, width: usize) -> Self {
+        return Self {
+            cpu,
+            width,
+            breakpoints: vec![],
+            stopped_at_cycle: None,
+            row: String::new(),
+        };
+    }
+
+    pub(crate) fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
+    /// Runs a single cycle, returning its number and the value of `X` while it ran.
+    pub(crate) fn step_cycle(&mut self) -> Option<(usize, i32)> {
+        let (cycle, x) = self.cpu.tick()?;
+        if (cycle - 1).is_multiple_of(self.width) {
+            self.row.clear();
+        }
+        self.row.push(if pixel_lit(cycle, x, self.width) {
+            '#'
+        } else {
+            '.'
+        });
+        return Some((cycle, x));
+    }
+
+    /// Runs cycles until the current instruction finishes, returning how many ran.
+    pub(crate) fn step_instruction(&mut self) -> usize {
+        let mut cycles = 0;
+        while self.step_cycle().is_some() {
+            cycles += 1;
+            if self.cpu.between_instructions() {
+                break;
+            }
+        }
+        return cycles;
+    }
+
+    /// Runs until a breakpoint is hit, returning it, or until the program finishes. Cycle
+    /// breakpoints are checked before each cycle runs, so resuming from one doesn't stop again
+    /// straight away.
+    pub(crate) fn resume(&mut self) -> Option<Breakpoint> {
+        let mut skip_cycle = self.stopped_at_cycle == Some(self.cpu.elapsed());
+        self.stopped_at_cycle = None;
+        while self.cpu.current().is_some() {
+            let next = Breakpoint::Cycle(self.cpu.elapsed() + 1);
+            if !skip_cycle && self.breakpoints.contains(&next) {
+                self.stopped_at_cycle = Some(self.cpu.elapsed());
+                return Some(next);
+            }
+            skip_cycle = false;
+
+            let (_, x_before) = self.step_cycle()?;
+            let changed = Breakpoint::X(self.cpu.x());
+            if self.cpu.x() != x_before && self.breakpoints.contains(&changed) {
+                return Some(changed);
+            }
+        }
+        return None;
+    }
+
+    /// Runs one debugger command, returning what it prints. The commands are `step [n]` and
+    /// `stepi [n]` to run cycles or instructions, `break cycle N` and `break x N`, `continue`,
+    /// and `print cycle`, `print x` or `print row`.
+    pub(crate) fn command(&mut self, line: &str) -> Result<String, String> {
+        let parts: Vec<&str> = line.split_ascii_whitespace().collect();
+        let count = |part: Option<&&str>| match part {
+            None => return Ok(1),
+            Some(count) => {
+                return count
+                    .parse::<usize>()
+                    .map_err(|_| format!("Expected a count, found {:?}", count));
+            }
+        };
+
+        match parts.as_slice() {
+            ["step", rest @ ..] if rest.len() <= 1 => {
+                let mut ran = 0;
+                for _ in 0..count(rest.first())? {
+                    if self.step_cycle().is_some() {
+                        ran += 1;
                     }
                 }
-                "addx" => {
-                    cycle += 2;
-                    let rem = (cycle + 20) % 40;
-                    if rem <= 1 {
-                        score += acc * (cycle - rem);
-                    }
-                    acc += parts.next().unwrap().parse::<i32>().unwrap();
+                return Ok(format!("ran {} cycles", ran));
+            }
+            ["stepi", rest @ ..] if rest.len() <= 1 => {
+                let mut ran = 0;
+                for _ in 0..count(rest.first())? {
+                    ran += self.step_instruction();
+                }
+                return Ok(format!("ran {} cycles", ran));
+            }
+            ["break", kind, value] => {
+                let breakpoint = match *kind {
+                    "cycle" => value.parse().map(Breakpoint::Cycle).ok(),
+                    "x" => value.parse().map(Breakpoint::X).ok(),
+                    _ => None,
                 }
-                _ => panic!("unexpected instruction: {}", instruction),
+                .ok_or(format!("Unrecognized breakpoint: {:?}", line))?;
+                self.add_breakpoint(breakpoint);
+                return Ok(format!("breakpoint {:?}", breakpoint));
             }
+            ["continue"] => match self.resume() {
+                Some(breakpoint) => return Ok(format!("stopped at {:?}", breakpoint)),
+                None => return Ok("program finished".to_string()),
+            },
+            ["print", "cycle"] => return Ok(self.cpu.elapsed().to_string()),
+            ["print", "x"] => return Ok(self.cpu.x().to_string()),
+            ["print", "row"] => return Ok(self.row.clone()),
+            _ => return Err(format!("Unrecognized command: {:?}", line)),
         }
-        return score.to_string();
     }
 
-    fn part_2(&mut self) -> String {
-        let mut output = [' '; 245];
-        output[40] = '\n';
-        output[81] = '\n';
-        output[122] = '\n';
-        output[163] = '\n';
-        output[204] = '\n';
-
-        let mut cycle: usize = 0;
-        let mut acc: i32 = 1;
-
-        for instruction in &self.instructions {
-            let x = i32::try_from(cycle).unwrap() % 40;
-            if x >= acc - 1 && x <= acc + 1 {
-                output[cycle + (cycle / 40)] = '#';
-            }
+    /// Runs a command per line as each one is read, yielding what it prints or why it failed.
+    /// Blank lines are skipped.
+    pub(crate) fn run_script<'a, R: BufRead + 'a>(
+        &'a mut self,
+        script: R,
+    ) -> impl Iterator<Item = Result<String, String>> + 'a {
+        return script
+            .lines()
+            .filter(|line| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+            .map(|line| match line {
+                Ok(line) => return self.command(&line),
+                Err(error) => return Err(format!("Couldn't read the script: {}", error)),
+            });
+    }
+}
 
-            let mut parts = instruction.split_ascii_whitespace();
-            if parts.next().unwrap() == "addx" {
-                cycle += 1;
+pub(crate) struct Day10 {
+    program: Vec<Instruction>,
+    width: usize,
+    height: usize,
+}
 
-                let x = i32::try_from(cycle).unwrap() % 40;
-                if x >= acc - 1 && x <= acc + 1 {
-                    output[cycle + (cycle / 40)] = '#';
-                }
+impl Day10 {
+    pub(crate) fn new() -> Self {
+        return Self::with_crt(40, 6).unwrap();
+    }
 
-                acc += parts.next().unwrap().parse::<i32>().unwrap();
-            }
-            cycle += 1;
+    /// Reads the program for a CRT `width` pixels wide and `height` pixels high.
+    pub(crate) fn with_crt(width: usize, height: usize) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err(format!(
+                "A CRT needs at least one pixel each way, not {}x{}",
+                width, height
+            ));
         }
-        return output.iter().collect();
+        let program = crate::utils::stream_day_lines(10)
+            .enumerate()
+            .map(|(i, line)| {
+                return line
+                    .parse()
+                    .unwrap_or_else(|error| panic!("line {}: {}", i + 1, error));
+            })
+            .collect();
+        return Ok(Self {
+            program,
+            width,
+            height,
+        });
+    }
+
+    pub(crate) fn cpu(&self) -> Cpu {
+        return Cpu::new(self.program.clone());
+    }
+
+    pub(crate) fn debugger(&self) -> Debugger {
+        return Debugger::new(self.cpu(), self.width);
+    }
+
+    /// The sum of `cycle * X` at cycle 20 and every 40 cycles after it.
+    pub(crate) fn signal_strength(&self) -> i32 {
+        return self
+            .cpu()
+            .filter(|(cycle, _)| (cycle + 20) % 40 == 0)
+            .map(|(cycle, x)| cycle as i32 * x)
+            .sum();
+    }
+
+    fn draw_crt(&self) -> Vec<Vec<bool>> {
+        let mut pixels = vec![vec![false; self.width]; self.height];
+        for (cycle, x) in self.cpu().take(self.width * self.height) {
+            pixels[(cycle - 1) / self.width][(cycle - 1) % self.width] =
+                pixel_lit(cycle, x, self.width);
+        }
+        return pixels;
+    }
+
+    /// The CRT picture as `#` for lit pixels and spaces for dark ones.
+    pub(crate) fn render_crt(&self) -> String {
+        return self
+            .draw_crt()
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|lit| if *lit { '#' } else { ' ' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+
+    /// The letters drawn on the CRT.
+    pub(crate) fn read_crt(&self) -> Result<String, String> {
+        return crate::utils::ocr::recognize(&self.draw_crt());
+    }
+}
+
+impl super::Day for Day10 {
+    fn part_1(&mut self) -> String {
+        return self.signal_strength().to_string();
+    }
+
+    fn part_2(&mut self) -> String {
+        // the test program draws a pattern rather than letters, so fall back to the raw picture
+        return self.read_crt().unwrap_or_else(|_| self.render_crt());
     }
 }
 
@@ -91,4 +370,103 @@ mod test {
             "##  ##  ##  ##  ##  ##  ##  ##  ##  ##  \n###   ###   ###   ###   ###   ###   ### \n####    ####    ####    ####    ####    \n#####     #####     #####     #####     \n######      ######      ######      ####\n#######       #######       #######     "
         );
     }
+
+    #[test]
+    fn trace() {
+        let day = Day10::new();
+        let trace: Vec<_> = day.cpu().collect();
+        assert_eq!(trace.len(), 240);
+        assert_eq!(trace[19], (20, 21));
+        assert_eq!(trace[219], (220, 18));
+
+        let mut cpu = Cpu::new(vec![
+            Instruction::Noop,
+            "addx 3".parse().unwrap(),
+            "addx -5".parse().unwrap(),
+        ]);
+        assert_eq!(
+            cpu.by_ref().collect::<Vec<_>>(),
+            vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]
+        );
+        assert_eq!(cpu.x(), -1);
+        assert!("addx".parse::<Instruction>().is_err());
+
+        // the sprite has drifted past the end of the second, narrower row
+        assert!(Day10::with_crt(0, 6).is_err());
+        assert!(Day10::with_crt(40, 0).is_err());
+        let day = Day10::with_crt(20, 2).unwrap();
+        assert_eq!(
+            day.render_crt(),
+            "##  ##  ##  ##  ##  \n                    "
+        );
+    }
+
+    #[test]
+    fn debugger() {
+        let day = Day10::new();
+        let mut debugger = day.debugger();
+        let output: Vec<String> = debugger
+            .run_script(
+                "break cycle 20\ncontinue\nprint cycle\nprint x\nstep\nprint row\nstepi 2\nbreak x 7\ncontinue\nprint cycle"
+                    .as_bytes(),
+            )
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            output,
+            vec![
+                "breakpoint Cycle(20)",
+                "stopped at Cycle(20)",
+                "19",
+                "21",
+                "ran 1 cycles",
+                "##..##..##..##..##..",
+                "ran 3 cycles",
+                "breakpoint X(7)",
+                "stopped at X(7)",
+                "45",
+            ]
+        );
+        assert!(debugger.command("jump").is_err());
+
+        // commands before a bad one still run and report what they did
+        let output: Vec<_> = day
+            .debugger()
+            .run_script("step 2\n\njump\nprint cycle".as_bytes())
+            .collect();
+        assert_eq!(
+            output,
+            vec![
+                Ok("ran 2 cycles".to_string()),
+                Err("Unrecognized command: \"jump\"".to_string()),
+                Ok("2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn early_breakpoints() {
+        let day = Day10::new();
+        let mut debugger = day.debugger();
+        let output: Vec<String> = debugger
+            .run_script(
+                "break cycle 1\ncontinue\nprint cycle\nbreak cycle 2\ncontinue\nprint cycle\ncontinue\nprint cycle"
+                    .as_bytes(),
+            )
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            output,
+            vec![
+                "breakpoint Cycle(1)",
+                "stopped at Cycle(1)",
+                "0",
+                "breakpoint Cycle(2)",
+                "stopped at Cycle(2)",
+                "1",
+                "program finished",
+                "240",
+            ]
+        );
+    }
 }
@@ -1,21 +1,84 @@
 use std::collections::VecDeque;
 
-use lazy_static::lazy_static;
-use regex::Regex;
+use crate::utils::parse::{
+    blocks, delimited, from_fn, int, literal, many, parse_complete, preceded, separated,
+    take_while, Failure, PResult, Parser,
+};
 
-#[derive(Debug)]
-enum Rhs {
+/// The right hand side of a monkey's `new = ...` operation.
+#[derive(Debug, PartialEq, Eq)]
+enum Expr {
     Old,
     Val(u64),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Works out the new worry level, keeping it below `modulus` if there is one. Without a
+    /// modulus, subtraction stops at zero as worry levels can't go negative. That can't be
+    /// done once levels wrap around a modulus, so subtracting with one is an error.
+    fn eval(&self, old: u64, modulus: Option<u64>) -> Result<u64, String> {
+        let reduce = |val: u128| match modulus {
+            Some(modulus) => return Ok((val % modulus as u128) as u64),
+            None => {
+                return u64::try_from(val).map_err(|_| format!("Worry level {} is too large", val))
+            }
+        };
+
+        match self {
+            Expr::Old => return reduce(old as u128),
+            Expr::Val(val) => return reduce(*val as u128),
+            Expr::Add(lhs, rhs) => {
+                return reduce(lhs.eval(old, modulus)? as u128 + rhs.eval(old, modulus)? as u128);
+            }
+            Expr::Mul(lhs, rhs) => {
+                return reduce(lhs.eval(old, modulus)? as u128 * rhs.eval(old, modulus)? as u128);
+            }
+            Expr::Sub(lhs, rhs) => {
+                if modulus.is_some() {
+                    return Err("Worry levels can't be subtracted under a modulus".to_string());
+                }
+                return Ok(lhs.eval(old, None)?.saturating_sub(rhs.eval(old, None)?));
+            }
+        }
+    }
+
+    fn subtracts(&self) -> bool {
+        match self {
+            Expr::Old | Expr::Val(_) => return false,
+            Expr::Sub(_, _) => return true,
+            Expr::Add(lhs, rhs) | Expr::Mul(lhs, rhs) => return lhs.subtracts() || rhs.subtracts(),
+        }
+    }
+}
+
+/// How a monkey decides who to throw an item to.
+#[derive(Debug, PartialEq, Eq)]
+enum Test {
+    DivisibleBy(u64),
+    GreaterThan(u64),
+    LessThan(u64),
+    EqualTo(u64),
+}
+
+impl Test {
+    fn matches(&self, val: u64) -> bool {
+        match self {
+            Test::DivisibleBy(divisor) => return val.is_multiple_of(*divisor),
+            Test::GreaterThan(bound) => return val > *bound,
+            Test::LessThan(bound) => return val < *bound,
+            Test::EqualTo(expected) => return val == *expected,
+        }
+    }
 }
 
 #[derive(Debug)]
 struct Monkey {
-    inspection_count: usize,
     held_items: VecDeque<u64>,
-    operator: char,
-    rhs: Rhs,
-    test: u64,
+    operation: Expr,
+    test: Test,
     true_target: usize,
     false_target: usize,
 }
@@ -24,120 +87,258 @@ pub(crate) struct Day11 {
     monkeys: Vec<Monkey>,
 }
 
-lazy_static! {
-    static ref STARTING_ITEMS: Regex = Regex::new(r"\d{2}").unwrap();
-    static ref OPERATION: Regex =
-        Regex::new(r"^  Operation: new = old (\+|\*) (old|\d+)$").unwrap();
-    static ref TEST: Regex = Regex::new(r"^  Test: divisible by (\d+)$").unwrap();
-    static ref TARGET: Regex =
-        Regex::new(r"^    If (?:true|false): throw to monkey (\d+)$").unwrap();
+// an operator symbol after any spaces, which consumes nothing if the symbol isn't there
+fn operator(symbol: &'static str) -> impl Parser<()> {
+    return from_fn(
+        move |input| match input.trim_start_matches(' ').strip_prefix(symbol) {
+            Some(rest) => return Ok(((), rest)),
+            None => return Err(Failure::new(input, symbol)),
+        },
+    );
 }
 
-impl Day11 {
-    pub(crate) fn new() -> Self {
-        let monkeys = crate::utils::read_day_grouped_lines(11)
-            .iter()
-            .map(|lines| {
-                let held_items = STARTING_ITEMS
-                    .captures_iter(&lines[1])
-                    .map(|capture| capture[0].to_string().parse().unwrap())
-                    .collect();
-
-                let operation = OPERATION.captures(&lines[2]).unwrap();
-                let operator = operation[1].chars().next().unwrap();
-                let rhs;
-                if operation[2] == *"old" {
-                    rhs = Rhs::Old;
+fn atom(input: &str) -> PResult<'_, Expr> {
+    let value = literal("old")
+        .map(|_| Expr::Old)
+        .or(int().map(Expr::Val))
+        .or(delimited(literal("("), expr, operator(")")))
+        .expect("old, a number or a bracketed expression");
+    return preceded(take_while(|char| char == ' '), value).parse(input);
+}
+
+fn product(input: &str) -> PResult<'_, Expr> {
+    return from_fn(atom)
+        .then(many(preceded(operator("*"), atom)))
+        .map(|(first, rest)| {
+            return rest
+                .into_iter()
+                .fold(first, |lhs, rhs| Expr::Mul(Box::new(lhs), Box::new(rhs)));
+        })
+        .parse(input);
+}
+
+fn expr(input: &str) -> PResult<'_, Expr> {
+    let sign = operator("+").map(|_| true).or(operator("-").map(|_| false));
+    return from_fn(product)
+        .then(many(sign.then(product)))
+        .map(|(first, rest)| {
+            return rest.into_iter().fold(first, |lhs, (is_add, rhs)| {
+                if is_add {
+                    return Expr::Add(Box::new(lhs), Box::new(rhs));
                 } else {
-                    rhs = Rhs::Val(operation[2].parse().unwrap());
+                    return Expr::Sub(Box::new(lhs), Box::new(rhs));
                 }
+            });
+        })
+        .parse(input);
+}
 
-                let test = TEST.captures(&lines[3]).unwrap()[1].parse().unwrap();
+fn test(input: &str) -> PResult<'_, Test> {
+    return preceded(literal("divisible by "), int())
+        .map(Test::DivisibleBy)
+        .or(preceded(literal("greater than "), int()).map(Test::GreaterThan))
+        .or(preceded(literal("less than "), int()).map(Test::LessThan))
+        .or(preceded(literal("equal to "), int()).map(Test::EqualTo))
+        .expect("a test like \"divisible by 3\"")
+        .parse(input);
+}
 
-                let true_target = TARGET.captures(&lines[4]).unwrap()[1].parse().unwrap();
-                let false_target = TARGET.captures(&lines[5]).unwrap()[1].parse().unwrap();
+fn monkey(input: &str) -> PResult<'_, Monkey> {
+    let (_, input) = delimited(literal("Monkey "), int::<usize>(), literal(":\n")).parse(input)?;
+    let (held_items, input) = delimited(
+        literal("  Starting items: "),
+        separated(int(), literal(", ")).map(VecDeque::from),
+        literal("\n"),
+    )
+    .parse(input)?;
+    let (operation, input) =
+        delimited(literal("  Operation: new ="), expr, literal("\n")).parse(input)?;
+    let (test, input) = delimited(literal("  Test: "), test, literal("\n")).parse(input)?;
+    let (true_target, input) = delimited(
+        literal("    If true: throw to monkey "),
+        int(),
+        literal("\n"),
+    )
+    .parse(input)?;
+    let (false_target, input) =
+        preceded(literal("    If false: throw to monkey "), int()).parse(input)?;
 
-                Monkey {
-                    inspection_count: 0,
-                    held_items,
-                    operator,
-                    rhs,
-                    test,
-                    true_target,
-                    false_target,
-                }
-            })
-            .collect();
+    return Ok((
+        Monkey {
+            held_items,
+            operation,
+            test,
+            true_target,
+            false_target,
+        },
+        input,
+    ));
+}
+
+/// How worry levels are kept manageable after each inspection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Relief {
+    /// Worry levels are divided by this, rounding down.
+    DivideBy(u64),
+    /// Worry levels are kept modulo the lowest common multiple of every monkey's divisor, which
+    /// leaves each divisibility test unchanged as long as no operation subtracts.
+    Modulus,
+}
+
+/// Where things stand at the end of a round.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RoundStats {
+    pub(crate) round: usize,
+    /// How many items each monkey inspected during this round.
+    pub(crate) inspections: Vec<usize>,
+    /// How many items each monkey has inspected over every round so far.
+    pub(crate) total_inspections: Vec<usize>,
+    /// The worry levels of the items each monkey holds, in the order they'll be inspected.
+    pub(crate) items: Vec<Vec<u64>>,
+}
+
+impl Day11 {
+    pub(crate) fn new() -> Self {
+        let input = crate::utils::read_day(11);
+        let monkeys = parse_complete(blocks(monkey), &input)
+            .unwrap_or_else(|error| panic!("Malformed monkey notes at {}", error));
 
         Self { monkeys }
     }
-}
 
-impl super::Day for Day11 {
-    fn part_1(&mut self) -> String {
-        for _ in 0..20 {
-            for i in 0..self.monkeys.len() {
-                while let Some(mut val) = self.monkeys[i].held_items.pop_front() {
-                    self.monkeys[i].inspection_count += 1;
-
-                    match (&self.monkeys[i].operator, &self.monkeys[i].rhs) {
-                        ('+', Rhs::Old) => val += val,
-                        ('*', Rhs::Old) => val *= val,
-                        ('+', Rhs::Val(modifier)) => val += *modifier,
-                        ('*', Rhs::Val(modifier)) => val *= *modifier,
-                        _ => panic!("Unrecognized operator: {}", self.monkeys[i].operator),
-                    }
-                    val /= 3;
-
-                    let target;
-                    if val % self.monkeys[i].test == 0 {
-                        target = self.monkeys[i].true_target;
-                    } else {
-                        target = self.monkeys[i].false_target;
-                    }
-                    self.monkeys[target].held_items.push_back(val);
+    /// Plays `rounds` rounds from the starting items, yielding the state after each one. The
+    /// parsed notes are left as they are, so this can be run any number of times. If a worry
+    /// level gets too large, the error is yielded and no more rounds are played.
+    pub(crate) fn rounds(
+        &self,
+        relief: Relief,
+        rounds: usize,
+    ) -> Result<impl Iterator<Item = Result<RoundStats, String>> + '_, String> {
+        let modulus = match relief {
+            Relief::DivideBy(0) => return Err("Worry levels can't be divided by 0".to_string()),
+            Relief::DivideBy(_) => None,
+            Relief::Modulus => Some(self.divisor_lcm()?),
+        };
+        let mut items: Vec<VecDeque<u64>> = self
+            .monkeys
+            .iter()
+            .map(|monkey| monkey.held_items.clone())
+            .collect();
+        let mut total_inspections = vec![0; self.monkeys.len()];
+        let mut failed = false;
+
+        return Ok((1..=rounds).map_while(move |round| {
+            if failed {
+                return None;
+            }
+            let inspections = match self.play_round(&mut items, relief, modulus) {
+                Ok(inspections) => inspections,
+                Err(error) => {
+                    failed = true;
+                    return Some(Err(format!("round {}: {}", round, error)));
                 }
+            };
+
+            for (total, count) in total_inspections.iter_mut().zip(&inspections) {
+                *total += count;
             }
-        }
-        self.monkeys
-            .sort_by_cached_key(|monkey| monkey.inspection_count);
-        self.monkeys.reverse();
+            return Some(Ok(RoundStats {
+                round,
+                inspections,
+                total_inspections: total_inspections.clone(),
+                items: items
+                    .iter()
+                    .map(|held| held.iter().copied().collect())
+                    .collect(),
+            }));
+        }));
+    }
+
+    // every monkey takes its turn, returning how many items each one inspected
+    fn play_round(
+        &self,
+        items: &mut [VecDeque<u64>],
+        relief: Relief,
+        modulus: Option<u64>,
+    ) -> Result<Vec<usize>, String> {
+        let mut inspections = vec![0; self.monkeys.len()];
+        for (i, monkey) in self.monkeys.iter().enumerate() {
+            while let Some(val) = items[i].pop_front() {
+                inspections[i] += 1;
 
-        return (self.monkeys[0].inspection_count * self.monkeys[1].inspection_count).to_string();
+                let val = match relief {
+                    Relief::DivideBy(divisor) => monkey.operation.eval(val, None)? / divisor,
+                    Relief::Modulus => monkey.operation.eval(val, modulus)?,
+                };
+                let target = if monkey.test.matches(val) {
+                    monkey.true_target
+                } else {
+                    monkey.false_target
+                };
+                items[target].push_back(val);
+            }
+        }
+        return Ok(inspections);
     }
 
-    fn part_2(&mut self) -> String {
-        let modder = self.monkeys.iter().fold(1, |acc, monkey| acc * monkey.test);
-        for _ in 0..10_000 {
-            for i in 0..self.monkeys.len() {
-                while let Some(mut val) = self.monkeys[i].held_items.pop_front() {
-                    self.monkeys[i].inspection_count += 1;
-
-                    match (&self.monkeys[i].operator, &self.monkeys[i].rhs) {
-                        ('+', Rhs::Old) => val += val,
-                        ('*', Rhs::Old) => val *= val,
-                        ('+', Rhs::Val(modifier)) => val += *modifier,
-                        ('*', Rhs::Val(modifier)) => val *= *modifier,
-                        _ => panic!("Unrecognized operator: {}", self.monkeys[i].operator),
-                    }
-
-                    val %= modder;
-                    let target;
-                    if val % self.monkeys[i].test == 0 {
-                        target = self.monkeys[i].true_target;
-                    } else {
-                        target = self.monkeys[i].false_target;
-                    }
-                    self.monkeys[target].held_items.push_back(val);
+    // the smallest modulus that leaves the outcome of every divisibility test unchanged
+    fn divisor_lcm(&self) -> Result<u64, String> {
+        let mut lcm: u64 = 1;
+        for (i, monkey) in self.monkeys.iter().enumerate() {
+            let divisor = match monkey.test {
+                Test::DivisibleBy(0) => {
+                    return Err(format!("Monkey {} tests for divisibility by 0", i));
+                }
+                Test::DivisibleBy(_) if monkey.operation.subtracts() => {
+                    return Err(format!(
+                        "Monkey {} subtracts, so worry levels can't be kept down with a modulus",
+                        i
+                    ));
                 }
+                Test::DivisibleBy(divisor) => divisor,
+                _ => {
+                    return Err(format!(
+                        "Monkey {} doesn't test for divisibility, so worry levels can't be kept down",
+                        i
+                    ));
+                }
+            };
+            let (mut a, mut b) = (lcm, divisor);
+            while b != 0 {
+                (a, b) = (b, a % b);
             }
+            lcm = (lcm / a)
+                .checked_mul(divisor)
+                .ok_or("The monkeys' divisors have too large a common multiple")?;
         }
+        return Ok(lcm);
+    }
+
+    /// The product of the two highest inspection counts after `rounds` rounds.
+    pub(crate) fn monkey_business(&self, relief: Relief, rounds: usize) -> Result<usize, String> {
+        let mut totals = self
+            .rounds(relief, rounds)?
+            .last()
+            .transpose()?
+            .map_or(vec![0; self.monkeys.len()], |stats| stats.total_inspections);
+        totals.sort_unstable_by(|a, b| b.cmp(a));
+        return Ok(totals.iter().take(2).product());
+    }
+}
 
-        self.monkeys
-            .sort_by_cached_key(|monkey| monkey.inspection_count);
-        self.monkeys.reverse();
+impl super::Day for Day11 {
+    fn part_1(&mut self) -> String {
+        return self
+            .monkey_business(Relief::DivideBy(3), 20)
+            .unwrap_or_else(|error| panic!("{}", error))
+            .to_string();
+    }
 
-        return (self.monkeys[0].inspection_count * self.monkeys[1].inspection_count).to_string();
+    fn part_2(&mut self) -> String {
+        return self
+            .monkey_business(Relief::Modulus, 10_000)
+            .unwrap_or_else(|error| panic!("{}", error))
+            .to_string();
     }
 }
 
@@ -157,4 +358,119 @@ mod test {
         let mut day = Day11::new();
         assert_eq!(day.part_2(), "2713310158");
     }
+
+    #[test]
+    fn expressions() {
+        let parse = |str| parse_complete(from_fn(expr), str).unwrap();
+        assert_eq!(parse(" old * old + 3").eval(4, None), Ok(19));
+        // without a modulus, worry levels stop at zero rather than going negative
+        assert_eq!(parse(" 2 * (old - 2)").eval(1, None), Ok(0));
+        assert_eq!(parse(" old - 5").eval(3, None), Ok(0));
+        assert!(parse(" old - 2").eval(1, Some(7)).is_err());
+        assert!(parse(" old * old").eval(u64::MAX, None).is_err());
+        assert_eq!(
+            parse_complete(from_fn(expr), " old % 2")
+                .err()
+                .unwrap()
+                .to_string(),
+            "line 1, column 5: expected end of input, found \" % 2\""
+        );
+
+        let notes = "Monkey 0:\n  Starting items: 5\n  Operation: new = old - 2\n  Test: greater than 2\n    If true: throw to monkey 1\n    If false: throw to monkey 1";
+        let (monkey, _) = monkey(notes).unwrap();
+        assert_eq!(monkey.test, Test::GreaterThan(2));
+        assert!(monkey.test.matches(monkey.operation.eval(5, None).unwrap()));
+    }
+
+    #[test]
+    fn round_stats() {
+        let day = Day11::new();
+        let first = day
+            .rounds(Relief::DivideBy(3), 20)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            first.items,
+            vec![
+                vec![20, 23, 27, 26],
+                vec![2080, 25, 167, 207, 401, 1046],
+                vec![],
+                vec![]
+            ]
+        );
+        assert_eq!(first.inspections, vec![2, 4, 3, 5]);
+
+        let last = day
+            .rounds(Relief::Modulus, 20)
+            .unwrap()
+            .last()
+            .unwrap()
+            .unwrap();
+        assert_eq!(last.round, 20);
+        assert_eq!(last.total_inspections, vec![99, 97, 8, 103]);
+        // running again starts from the same notes
+        assert_eq!(day.monkey_business(Relief::DivideBy(3), 20), Ok(10605));
+    }
+
+    #[test]
+    fn unusable_relief() {
+        let notes = "Monkey 0:\n  Starting items: 5\n  Operation: new = old - 2\n  Test: greater than 2\n    If true: throw to monkey 0\n    If false: throw to monkey 0";
+        let day = Day11 {
+            monkeys: parse_complete(blocks(monkey), notes).unwrap(),
+        };
+        assert_eq!(
+            day.monkey_business(Relief::Modulus, 20),
+            Err(
+                "Monkey 0 doesn't test for divisibility, so worry levels can't be kept down"
+                    .to_string()
+            )
+        );
+        assert!(day.rounds(Relief::DivideBy(0), 1).is_err());
+
+        let day = Day11 {
+            monkeys: vec![u64::MAX, u64::MAX - 1]
+                .into_iter()
+                .map(|divisor| Monkey {
+                    held_items: VecDeque::new(),
+                    operation: Expr::Old,
+                    test: Test::DivisibleBy(divisor),
+                    true_target: 0,
+                    false_target: 0,
+                })
+                .collect(),
+        };
+        assert_eq!(
+            day.monkey_business(Relief::Modulus, 1),
+            Err("The monkeys' divisors have too large a common multiple".to_string())
+        );
+
+        let notes = "Monkey 0:\n  Starting items: 1\n  Operation: new = old - 2\n  Test: divisible by 3\n    If true: throw to monkey 0\n    If false: throw to monkey 0";
+        let day = Day11 {
+            monkeys: parse_complete(blocks(monkey), notes).unwrap(),
+        };
+        assert_eq!(
+            day.monkey_business(Relief::Modulus, 1),
+            Err(
+                "Monkey 0 subtracts, so worry levels can't be kept down with a modulus".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn worry_overflow() {
+        let notes = "Monkey 0:\n  Starting items: 3\n  Operation: new = old * old\n  Test: divisible by 2\n    If true: throw to monkey 1\n    If false: throw to monkey 1\n\nMonkey 1:\n  Starting items: \n  Operation: new = old\n  Test: divisible by 2\n    If true: throw to monkey 0\n    If false: throw to monkey 0";
+        let day = Day11 {
+            monkeys: parse_complete(blocks(monkey), notes).unwrap(),
+        };
+        let played: Vec<_> = day.rounds(Relief::DivideBy(1), 10).unwrap().collect();
+        assert_eq!(played.len(), 6);
+        assert!(played[..5].iter().all(Result::is_ok));
+        assert_eq!(
+            played[5],
+            Err("round 6: Worry level 3433683820292512484657849089281 is too large".to_string())
+        );
+        assert!(day.monkey_business(Relief::DivideBy(1), 10).is_err());
+    }
 }
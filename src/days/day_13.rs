@@ -1,3 +1,7 @@
+use crate::utils::parse::{
+    blocks, delimited, int, literal, parse_complete, separated, PResult, Parser,
+};
+
 #[derive(Debug, PartialEq)]
 enum Entry {
     Num(u8),
@@ -8,61 +12,19 @@ pub(crate) struct Day13 {
     vals: Vec<(Entry, Entry)>,
 }
 
-impl Day13 {
-    fn parse_line(chars: &mut std::str::Chars) -> Entry {
-        let mut vals: Vec<Entry> = vec![];
-        let mut should_add_val = false;
-        let mut val = 0;
-        while let Some(char) = {
-            let next = chars.next();
-            match next {
-                Some(']') => None,
-                _ => next,
-            }
-        } {
-            match char {
-                '0' => {
-                    should_add_val = true;
-                    val *= 10;
-                }
-                ',' => {
-                    vals.push(Entry::Num(val));
-                    val = 0;
-                    should_add_val = false;
-                }
-                '[' => {
-                    vals.push(Self::parse_line(chars));
-                    chars.next();
-                }
-                '1'..='9' => {
-                    should_add_val = true;
-                    val = u8::try_from(char).unwrap() - 48;
-                }
-                _ => {
-                    panic!("Unexpected character: {}", char);
-                }
-            }
-        }
-        if should_add_val {
-            vals.push(Entry::Num(val));
-        }
-        return Entry::Sub(vals);
-    }
+fn entry(input: &str) -> PResult<'_, Entry> {
+    return int()
+        .map(Entry::Num)
+        .or(delimited(literal("["), separated(entry, literal(",")), literal("]")).map(Entry::Sub))
+        .expect("packet")
+        .parse(input);
+}
 
+impl Day13 {
     pub(crate) fn new() -> Self {
-        let vals = crate::utils::read_day_grouped_lines(13)
-            .iter()
-            .map(|lines| {
-                let mut lhs_chars = lines[0].chars();
-                let mut rhs_chars = lines[1].chars();
-                lhs_chars.next();
-                rhs_chars.next();
-                (
-                    Self::parse_line(&mut lhs_chars),
-                    Self::parse_line(&mut rhs_chars),
-                )
-            })
-            .collect();
+        let input = crate::utils::read_day(13);
+        let vals = parse_complete(blocks(entry.skip(literal("\n")).then(entry)), &input)
+            .unwrap_or_else(|error| panic!("Malformed packets at {}", error));
 
         return Self { vals };
     }
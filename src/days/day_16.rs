@@ -3,8 +3,12 @@ use std::collections::HashMap;
 use lazy_static::lazy_static;
 use regex::Regex;
 
-type NodeIndex = u64;
+use crate::utils::bitset::FixedBitSet;
+
+type NodeIndex = usize;
 type Time = i64;
+// which valves are open, copied into every search state so it must stay small
+type ValveSet = FixedBitSet<2>;
 
 #[derive(Clone, Debug)]
 struct Node {
@@ -24,35 +28,36 @@ lazy_static! {
     .unwrap();
 }
 
-fn get_index(str: &String, indexes: &mut HashMap<String, NodeIndex>, index: &mut u8) -> NodeIndex {
-    if !indexes.contains_key(str) {
-        indexes.insert(str.to_owned(), 1 << *index);
-        *index += 1;
-    }
-
-    *indexes.get(str).unwrap()
+fn get_index(str: &String, indexes: &mut HashMap<String, NodeIndex>) -> NodeIndex {
+    let next_index = indexes.len();
+    *indexes.entry(str.to_owned()).or_insert(next_index)
 }
 
 impl Day16 {
     pub(crate) fn new() -> Self {
         let mut nodes: HashMap<NodeIndex, Node> = HashMap::new();
         let mut indexes: HashMap<String, NodeIndex> = HashMap::new();
-        let mut index = 0;
-
         for line in crate::utils::read_day_lines(16).iter() {
             let captures = REGEX.captures(line).unwrap();
             nodes.insert(
-                get_index(&captures[1].to_owned(), &mut indexes, &mut index),
+                get_index(&captures[1].to_owned(), &mut indexes),
                 Node {
                     flow: captures[2].parse().unwrap(),
                     neighbours: captures[3]
                         .split(", ")
-                        .map(|str| get_index(&str.to_owned(), &mut indexes, &mut index))
+                        .map(|str| get_index(&str.to_owned(), &mut indexes))
                         .collect(),
                 },
             );
         }
 
+        assert!(
+            nodes.len() <= ValveSet::CAPACITY,
+            "Only up to {} valves are supported, found {}",
+            ValveSet::CAPACITY,
+            nodes.len()
+        );
+
         return Self {
             nodes,
             start_id: *indexes.get(&"AA".to_owned()).unwrap(),
@@ -60,14 +65,14 @@ impl Day16 {
     }
 }
 
-struct P1State(NodeIndex, NodeIndex, i64);
-struct P2State(NodeIndex, NodeIndex, NodeIndex, i64);
+struct P1State(NodeIndex, ValveSet, i64);
+struct P2State(NodeIndex, NodeIndex, ValveSet, i64);
 
 impl super::Day for Day16 {
     fn part_1(&mut self) -> String {
         let run_time: Time = 30;
-        let mut states: Vec<P1State> = vec![P1State(self.start_id, 0, 0)];
-        let mut best: HashMap<(NodeIndex, NodeIndex), i64> = HashMap::new();
+        let mut states: Vec<P1State> = vec![P1State(self.start_id, ValveSet::new(), 0)];
+        let mut best: HashMap<(NodeIndex, ValveSet), i64> = HashMap::new();
 
         for time in 1..=run_time {
             let mut new_states: Vec<P1State> = vec![];
@@ -78,20 +83,23 @@ impl super::Day for Day16 {
                     continue;
                 }
 
-                best.insert(key, pressure);
                 let node = self.nodes.get(&node_index).unwrap();
 
-                if node_index & opened_mask == 0 && node.flow > 0 {
+                if !key.1.contains(node_index) && node.flow > 0 {
+                    let mut opened_mask = key.1;
+                    opened_mask.insert(node_index);
                     new_states.push(P1State(
                         node_index,
-                        opened_mask | node_index,
+                        opened_mask,
                         pressure + node.flow * (run_time - time),
                     ));
                 }
 
                 for neighbour in node.neighbours.iter() {
-                    new_states.push(P1State(*neighbour, opened_mask, pressure));
+                    new_states.push(P1State(*neighbour, key.1, pressure));
                 }
+
+                best.insert(key, pressure);
             }
 
             states = new_states;
@@ -102,8 +110,9 @@ impl super::Day for Day16 {
 
     fn part_2(&mut self) -> String {
         let run_time: Time = 26;
-        let mut states: Vec<P2State> = vec![P2State(self.start_id, self.start_id, 0, 0)];
-        let mut best: HashMap<(NodeIndex, NodeIndex, NodeIndex), i64> = HashMap::new();
+        let mut states: Vec<P2State> =
+            vec![P2State(self.start_id, self.start_id, ValveSet::new(), 0)];
+        let mut best: HashMap<(NodeIndex, NodeIndex, ValveSet), i64> = HashMap::new();
 
         for time in 1..=run_time {
             println!("{}, {}", time, states.len());
@@ -119,22 +128,27 @@ impl super::Day for Day16 {
                 if *best.get(&key).unwrap_or(&-1) >= pressure {
                     continue;
                 }
-                best.insert(key, pressure);
 
                 let node_1 = self.nodes.get(&node_index_1).unwrap();
                 let node_2 = self.nodes.get(&node_index_2).unwrap();
 
-                let can_open_1 = node_index_1 & opened_mask == 0 && node_1.flow > 0;
+                let opened_mask = key.2;
+                let can_open_1 = !opened_mask.contains(node_index_1) && node_1.flow > 0;
                 let can_open_2 = (node_index_1 != node_index_2)
-                    && (node_index_2 & opened_mask == 0)
+                    && !opened_mask.contains(node_index_2)
                     && (node_2.flow > 0);
 
+                let mut opened_mask_1 = opened_mask;
+                opened_mask_1.insert(node_index_1);
+                let mut opened_mask_2 = opened_mask;
+                opened_mask_2.insert(node_index_2);
+
                 if can_open_1 {
                     if can_open_2 {
                         new_states.push(P2State(
                             node_index_1,
                             node_index_2,
-                            opened_mask | node_index_1 | node_index_2,
+                            opened_mask_1 | opened_mask_2,
                             pressure
                                 + node_1.flow * (run_time - time)
                                 + node_2.flow * (run_time - time),
@@ -144,7 +158,7 @@ impl super::Day for Day16 {
                         new_states.push(P2State(
                             node_index_1,
                             *neighbour,
-                            opened_mask | node_index_1,
+                            opened_mask_1,
                             pressure + node_1.flow * (run_time - time),
                         ));
                     }
@@ -155,7 +169,7 @@ impl super::Day for Day16 {
                         new_states.push(P2State(
                             *neighbour,
                             node_index_2,
-                            opened_mask | node_index_2,
+                            opened_mask_2,
                             pressure + node_2.flow * (run_time - time),
                         ));
                     }
@@ -166,6 +180,8 @@ impl super::Day for Day16 {
                         new_states.push(P2State(*neighbour_1, *neighbour_2, opened_mask, pressure));
                     }
                 }
+
+                best.insert(key, pressure);
             }
 
             states = new_states;
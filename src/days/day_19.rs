@@ -1,9 +1,6 @@
-use lazy_static::lazy_static;
-use regex::Regex;
-
-lazy_static! {
-    static ref REGEX: Regex = Regex::new(r"(\d+)").unwrap();
-}
+use crate::utils::parse::{
+    delimited, int, literal, many, parse_complete, preceded, whitespace, PResult, Parser,
+};
 
 #[derive(Debug)]
 struct Blueprint {
@@ -14,18 +11,28 @@ struct Blueprint {
     max_ore: usize,
 }
 
-impl Blueprint {
-    fn from_string(string: &String) -> Self {
-        let mut parts = REGEX
-            .captures_iter(string)
-            .map(|capture| capture[0].parse().unwrap());
-
-        parts.next();
-
-        let ore_robot_cost = parts.next().unwrap();
-        let clay_robot_cost = parts.next().unwrap();
-        let obsidian_robot_cost = (parts.next().unwrap(), parts.next().unwrap());
-        let geode_robot_cost = (parts.next().unwrap(), parts.next().unwrap());
+fn blueprint(input: &str) -> PResult<'_, Blueprint> {
+    // the example input wraps each robot onto its own line, so allow any whitespace between them
+    let cost = |robot: &'static str| preceded(whitespace(), preceded(literal(robot), int()));
+    let (_, input) = delimited(literal("Blueprint "), int::<usize>(), literal(":")).parse(input)?;
+    let (ore_robot_cost, input) = cost("Each ore robot costs ")
+        .skip(literal(" ore."))
+        .parse(input)?;
+    let (clay_robot_cost, input) = cost("Each clay robot costs ")
+        .skip(literal(" ore."))
+        .parse(input)?;
+    let (obsidian_robot_cost, input) = cost("Each obsidian robot costs ")
+        .skip(literal(" ore and "))
+        .then(int())
+        .skip(literal(" clay."))
+        .parse(input)?;
+    let (geode_robot_cost, input) = cost("Each geode robot costs ")
+        .skip(literal(" ore and "))
+        .then(int())
+        .skip(literal(" obsidian."))
+        .parse(input)?;
+
+    return Ok((
         Blueprint {
             ore_robot_cost,
             clay_robot_cost,
@@ -33,8 +40,9 @@ impl Blueprint {
             geode_robot_cost,
             max_ore: ore_robot_cost
                 .max(clay_robot_cost.max(obsidian_robot_cost.0.max(geode_robot_cost.0))),
-        }
-    }
+        },
+        input,
+    ));
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
@@ -142,10 +150,10 @@ pub(crate) struct Day19 {
 
 impl Day19 {
     pub(crate) fn new() -> Self {
-        let blueprints = crate::utils::read_day_lines(19)
-            .iter()
-            .map(Blueprint::from_string)
-            .collect();
+        let input = crate::utils::read_day(19);
+        let blueprints = parse_complete(many(preceded(whitespace(), blueprint)), &input)
+            .unwrap_or_else(|error| panic!("Malformed blueprints at {}", error))
+            .into_boxed_slice();
         return Self { blueprints };
     }
 }
@@ -1,4 +1,6 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::collections::HashMap;
+
+use crate::utils::arena::{Arena, Id};
 
 type Coord = (isize, isize);
 
@@ -9,7 +11,7 @@ struct Elf {
 }
 
 impl Elf {
-    fn is_lonely(&self, map: &HashMap<(isize, isize), Rc<RefCell<Elf>>>) -> bool {
+    fn is_lonely(&self, map: &HashMap<(isize, isize), Id<Elf>>) -> bool {
         for y in (self.pos.1 - 1)..=(self.pos.1 + 1) {
             for x in (self.pos.0 - 1)..=(self.pos.0 + 1) {
                 if x == self.pos.0 && y == self.pos.1 {
@@ -26,7 +28,7 @@ impl Elf {
     fn get_direction_move(
         &self,
         dir: &Dir,
-        map: &HashMap<(isize, isize), Rc<RefCell<Elf>>>,
+        map: &HashMap<(isize, isize), Id<Elf>>,
     ) -> Option<(isize, isize)> {
         let x = self.pos.0;
         let y = self.pos.1;
@@ -91,7 +93,7 @@ const DIRS: [[Dir; 4]; 4] = [
 ];
 
 pub(crate) struct Day23 {
-    elves: Box<[Rc<RefCell<Elf>>]>,
+    elves: Arena<Elf>,
 }
 
 impl Day23 {
@@ -103,10 +105,10 @@ impl Day23 {
                 line.chars()
                     .enumerate()
                     .filter_map(move |(x, char)| match char {
-                        '#' => Some(Rc::new(RefCell::new(Elf {
+                        '#' => Some(Elf {
                             pos: (isize::try_from(x).unwrap(), isize::try_from(y).unwrap()),
                             proposed_move: None,
-                        }))),
+                        }),
                         _ => None,
                     })
             })
@@ -120,7 +122,7 @@ trait ElfMap {
     fn get_empty_space(&self) -> usize;
 }
 
-impl ElfMap for HashMap<(isize, isize), Rc<RefCell<Elf>>> {
+impl ElfMap for HashMap<(isize, isize), Id<Elf>> {
     fn print_map(&self) {
         let min_x = self.keys().min_by_key(|coord| coord.0).unwrap().0;
         let min_y = self.keys().min_by_key(|coord| coord.1).unwrap().1;
@@ -148,11 +150,8 @@ impl ElfMap for HashMap<(isize, isize), Rc<RefCell<Elf>>> {
 
 impl super::Day for Day23 {
     fn part_1(&mut self) -> String {
-        let mut map: HashMap<(isize, isize), Rc<RefCell<Elf>>> = self
-            .elves
-            .iter()
-            .map(|elf| (elf.borrow().pos, Rc::clone(elf)))
-            .collect();
+        let mut map: HashMap<(isize, isize), Id<Elf>> =
+            self.elves.iter().map(|(id, elf)| (elf.pos, id)).collect();
 
         let mut dirs_iter = DIRS.iter().cycle();
         let mut i: usize = 0;
@@ -160,8 +159,7 @@ impl super::Day for Day23 {
         loop {
             let mut proposed_moves: HashMap<(isize, isize), bool> = HashMap::new();
             let dirs = dirs_iter.next().unwrap();
-            for elf_rc in self.elves.iter() {
-                let mut elf = elf_rc.borrow_mut();
+            for (_, elf) in self.elves.iter_mut() {
                 elf.proposed_move = None;
                 if elf.is_lonely(&map) {
                     continue;
@@ -179,13 +177,12 @@ impl super::Day for Day23 {
                 }
             }
 
-            for elf_rc in self.elves.iter() {
-                let mut elf = elf_rc.borrow_mut();
+            for (id, elf) in self.elves.iter_mut() {
                 if let Some(proposed_move) = elf.proposed_move {
                     if *proposed_moves.get(&proposed_move).unwrap() {
                         map.remove(&elf.pos);
                         elf.pos = proposed_move;
-                        map.insert(elf.pos, Rc::clone(elf_rc));
+                        map.insert(elf.pos, id);
                     }
                 }
             }
@@ -200,11 +197,8 @@ impl super::Day for Day23 {
     }
 
     fn part_2(&mut self) -> String {
-        let mut map: HashMap<(isize, isize), Rc<RefCell<Elf>>> = self
-            .elves
-            .iter()
-            .map(|elf| (elf.borrow().pos, Rc::clone(elf)))
-            .collect();
+        let mut map: HashMap<(isize, isize), Id<Elf>> =
+            self.elves.iter().map(|(id, elf)| (elf.pos, id)).collect();
 
         let mut dirs_iter = DIRS.iter().cycle();
         let mut i: usize = 0;
@@ -212,8 +206,7 @@ impl super::Day for Day23 {
         loop {
             let mut proposed_moves: HashMap<(isize, isize), bool> = HashMap::new();
             let dirs = dirs_iter.next().unwrap();
-            for elf_rc in self.elves.iter() {
-                let mut elf = elf_rc.borrow_mut();
+            for (_, elf) in self.elves.iter_mut() {
                 elf.proposed_move = None;
                 if elf.is_lonely(&map) {
                     continue;
@@ -232,13 +225,12 @@ impl super::Day for Day23 {
             }
 
             let mut any_moved = false;
-            for elf_rc in self.elves.iter() {
-                let mut elf = elf_rc.borrow_mut();
+            for (id, elf) in self.elves.iter_mut() {
                 if let Some(proposed_move) = elf.proposed_move {
                     if *proposed_moves.get(&proposed_move).unwrap() {
                         map.remove(&elf.pos);
                         elf.pos = proposed_move;
-                        map.insert(elf.pos, Rc::clone(elf_rc));
+                        map.insert(elf.pos, id);
                         any_moved = true;
                     }
                 }
@@ -1,4 +1,7 @@
-use std::ops::{Add, AddAssign, Index, IndexMut};
+use std::{
+    ops::{Add, AddAssign, Index, IndexMut},
+    str::FromStr,
+};
 
 #[derive(Clone, Copy, Debug)]
 enum Digit {
@@ -163,32 +166,36 @@ impl ToString for Number {
     }
 }
 
-pub(crate) struct Day25 {
-    nums: Box<[Number]>,
+impl FromStr for Number {
+    type Err = String;
+
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        let digits = str
+            .chars()
+            .map(Digit::try_from)
+            .rev()
+            .collect::<Result<_, _>>()?;
+        Ok(Number { digits })
+    }
 }
 
+pub(crate) struct Day25 {}
+
 impl Day25 {
     pub(crate) fn new() -> Self {
-        let nums = crate::utils::read_day_lines(25)
-            .iter()
-            .map(|line| Number {
-                digits: line
-                    .chars()
-                    .map(|char| Digit::try_from(char).unwrap())
-                    .rev()
-                    .collect(),
-            })
-            .collect();
-        return Self { nums };
+        return Self {};
     }
 }
 
 impl super::Day for Day25 {
     fn part_1(&mut self) -> String {
-        let mut total = self.nums[0].clone();
-        for num in self.nums[1..].iter() {
-            total += num;
-        }
+        // only the running total is kept, so the numbers are never all held at once
+        let total = crate::utils::stream_day_parsed_lines::<Number>(25)
+            .reduce(|mut total, num| {
+                total += &num;
+                total
+            })
+            .expect("Expected at least one number");
         total.to_string()
     }
 
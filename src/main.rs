@@ -1,11 +1,45 @@
 use crate::days::Day;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
 use std::process::ExitCode;
 
 mod days;
 mod utils;
 
 fn print_malformed_args() {
-    println!("Args are malformed, expected: aoc (day) (part)");
+    println!(
+        "Args are malformed, expected: aoc (day) (part), aoc 4 sweep or aoc 10 debug [script]"
+    );
+}
+
+// runs Day 10 debugger commands from a file, or one at a time from stdin if no file is given,
+// printing what each one does as it goes
+fn run_debugger(script_path: Option<&String>) -> Result<(), ExitCode> {
+    let script: Box<dyn BufRead> = match script_path {
+        Some(path) => match File::open(path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(error) => {
+                println!("Couldn't read the debugger script: {}", error);
+                return Err(ExitCode::FAILURE);
+            }
+        },
+        None => Box::new(std::io::stdin().lock()),
+    };
+
+    let mut debugger = days::day_10::Day10::new().debugger();
+    for output in debugger.run_script(script) {
+        match output {
+            Ok(output) => println!("{}", output),
+            // a typo shouldn't end an interactive session, but a script stops at it
+            Err(error) => {
+                println!("{}", error);
+                if script_path.is_some() {
+                    return Err(ExitCode::FAILURE);
+                }
+            }
+        }
+    }
+    return Ok(());
 }
 
 fn main() -> Result<(), ExitCode> {
@@ -15,6 +49,13 @@ fn main() -> Result<(), ExitCode> {
         return Err(ExitCode::FAILURE);
     }
     let day_num = args[1].parse::<u8>().unwrap_or(0);
+    if day_num == 4 && args[2] == "sweep" {
+        println!("{}", days::day_04::Day04::new().sweep_report());
+        return Ok(());
+    }
+    if day_num == 10 && args[2] == "debug" {
+        return run_debugger(args.get(3));
+    }
     let part_num = args[2].parse::<u8>().unwrap_or(0);
 
     let mut day: Box<dyn Day>;
@@ -0,0 +1,368 @@
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    ops::{Index, IndexMut},
+};
+
+/// A typed index into one of the containers in this module.
+pub(crate) struct Id<T> {
+    index: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Id<T> {
+    fn new(index: usize) -> Self {
+        return Self {
+            index,
+            marker: PhantomData,
+        };
+    }
+}
+
+// implemented by hand so that ids are Copy, Eq etc. regardless of what they point to
+impl<T> Clone for Id<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Id<T> {}
+
+impl<T> PartialEq for Id<T> {
+    fn eq(&self, other: &Self) -> bool {
+        return self.index == other.index;
+    }
+}
+
+impl<T> Eq for Id<T> {}
+
+impl<T> PartialOrd for Id<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl<T> Ord for Id<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        return self.index.cmp(&other.index);
+    }
+}
+
+impl<T> Hash for Id<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
+
+impl<T> Debug for Id<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "Id({})", self.index);
+    }
+}
+
+/// A pool of values that are never freed individually, addressed by [`Id`].
+#[derive(Clone, Debug)]
+pub(crate) struct Arena<T> {
+    items: Vec<T>,
+}
+
+impl<T> Arena<T> {
+    pub(crate) fn new() -> Self {
+        return Self { items: vec![] };
+    }
+
+    pub(crate) fn alloc(&mut self, value: T) -> Id<T> {
+        self.items.push(value);
+        return Id::new(self.items.len() - 1);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        return self.items.len();
+    }
+
+    pub(crate) fn ids(&self) -> impl Iterator<Item = Id<T>> {
+        return (0..self.items.len()).map(Id::new);
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (Id<T>, &T)> {
+        return self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (Id::new(i), item));
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = (Id<T>, &mut T)> {
+        return self
+            .items
+            .iter_mut()
+            .enumerate()
+            .map(|(i, item)| (Id::new(i), item));
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl<T> FromIterator<T> for Arena<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        return Self {
+            items: iter.into_iter().collect(),
+        };
+    }
+}
+
+impl<T> Index<Id<T>> for Arena<T> {
+    type Output = T;
+
+    fn index(&self, id: Id<T>) -> &T {
+        return &self.items[id.index];
+    }
+}
+
+impl<T> IndexMut<Id<T>> for Arena<T> {
+    fn index_mut(&mut self, id: Id<T>) -> &mut T {
+        return &mut self.items[id.index];
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TreeLinks<T> {
+    parent: Option<Id<T>>,
+    children: Vec<Id<T>>,
+}
+
+/// A rooted tree where each node knows its parent and its children in insertion order.
+#[derive(Clone, Debug)]
+pub(crate) struct Tree<T> {
+    values: Arena<T>,
+    links: Vec<TreeLinks<T>>,
+}
+
+impl<T> Tree<T> {
+    pub(crate) fn new(root: T) -> Self {
+        let mut values = Arena::new();
+        values.alloc(root);
+        return Self {
+            values,
+            links: vec![TreeLinks {
+                parent: None,
+                children: vec![],
+            }],
+        };
+    }
+
+    pub(crate) fn root(&self) -> Id<T> {
+        return Id::new(0);
+    }
+
+    pub(crate) fn add_child(&mut self, parent: Id<T>, value: T) -> Id<T> {
+        let id = self.values.alloc(value);
+        self.links.push(TreeLinks {
+            parent: Some(parent),
+            children: vec![],
+        });
+        self.links[parent.index].children.push(id);
+        return id;
+    }
+
+    pub(crate) fn parent(&self, id: Id<T>) -> Option<Id<T>> {
+        return self.links[id.index].parent;
+    }
+
+    pub(crate) fn children(&self, id: Id<T>) -> &[Id<T>] {
+        return &self.links[id.index].children;
+    }
+
+    /// Iterates `id` and all of its ancestors, ending at the root.
+    pub(crate) fn ancestors(&self, id: Id<T>) -> impl Iterator<Item = Id<T>> + '_ {
+        return std::iter::successors(Some(id), |id| self.parent(*id));
+    }
+
+    /// Iterates `id` and everything below it, parents before their children.
+    pub(crate) fn descendants(&self, id: Id<T>) -> impl Iterator<Item = Id<T>> + '_ {
+        let mut stack = vec![id];
+        return std::iter::from_fn(move || {
+            let id = stack.pop()?;
+            stack.extend(self.children(id).iter().rev());
+            return Some(id);
+        });
+    }
+}
+
+impl<T> Index<Id<T>> for Tree<T> {
+    type Output = T;
+
+    fn index(&self, id: Id<T>) -> &T {
+        return &self.values[id];
+    }
+}
+
+impl<T> IndexMut<Id<T>> for Tree<T> {
+    fn index_mut(&mut self, id: Id<T>) -> &mut T {
+        return &mut self.values[id];
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RingLinks<T> {
+    previous: Id<T>,
+    next: Id<T>,
+}
+
+/// A circular doubly linked list. Values keep their id for as long as the ring exists, even
+/// while they are unlinked and moved around.
+#[derive(Clone, Debug)]
+pub(crate) struct Ring<T> {
+    values: Arena<T>,
+    links: Vec<RingLinks<T>>,
+}
+
+impl<T> Ring<T> {
+    pub(crate) fn new() -> Self {
+        return Self {
+            values: Arena::new(),
+            links: vec![],
+        };
+    }
+
+    /// Adds `value` directly after the most recently pushed value.
+    pub(crate) fn push(&mut self, value: T) -> Id<T> {
+        let id = self.values.alloc(value);
+        if id.index == 0 {
+            self.links.push(RingLinks {
+                previous: id,
+                next: id,
+            });
+            return id;
+        }
+
+        let last = Id::new(id.index - 1);
+        self.links.push(RingLinks {
+            previous: last,
+            next: last,
+        });
+        self.insert_after(last, id);
+        return id;
+    }
+
+    pub(crate) fn next(&self, id: Id<T>) -> Id<T> {
+        return self.links[id.index].next;
+    }
+
+    pub(crate) fn previous(&self, id: Id<T>) -> Id<T> {
+        return self.links[id.index].previous;
+    }
+
+    /// Joins the neighbours of `id` together, leaving `id` pointing at its old neighbours.
+    pub(crate) fn unlink(&mut self, id: Id<T>) {
+        let RingLinks { previous, next } = self.links[id.index];
+        self.links[previous.index].next = next;
+        self.links[next.index].previous = previous;
+    }
+
+    /// Places an unlinked `id` directly after `anchor`.
+    pub(crate) fn insert_after(&mut self, anchor: Id<T>, id: Id<T>) {
+        let next = self.links[anchor.index].next;
+        self.links[id.index] = RingLinks {
+            previous: anchor,
+            next,
+        };
+        self.links[anchor.index].next = id;
+        self.links[next.index].previous = id;
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        return self.values.len();
+    }
+
+    pub(crate) fn ids(&self) -> impl Iterator<Item = Id<T>> {
+        return self.values.ids();
+    }
+
+    /// Iterates around the ring once, starting at `start`.
+    pub(crate) fn iter_from(&self, start: Id<T>) -> impl Iterator<Item = (Id<T>, &T)> {
+        let mut current = Some(start);
+        return std::iter::from_fn(move || {
+            let id = current?;
+            let next = self.next(id);
+            current = if next == start { None } else { Some(next) };
+            return Some((id, &self.values[id]));
+        });
+    }
+}
+
+impl<T> Default for Ring<T> {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl<T> FromIterator<T> for Ring<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut ring = Self::new();
+        for value in iter {
+            ring.push(value);
+        }
+        return ring;
+    }
+}
+
+impl<T> Index<Id<T>> for Ring<T> {
+    type Output = T;
+
+    fn index(&self, id: Id<T>) -> &T {
+        return &self.values[id];
+    }
+}
+
+impl<T> IndexMut<Id<T>> for Ring<T> {
+    fn index_mut(&mut self, id: Id<T>) -> &mut T {
+        return &mut self.values[id];
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tree() {
+        let mut tree = Tree::new("root");
+        let a = tree.add_child(tree.root(), "a");
+        let b = tree.add_child(a, "b");
+        let c = tree.add_child(tree.root(), "c");
+
+        assert_eq!(
+            tree.ancestors(b).collect::<Vec<_>>(),
+            vec![b, a, tree.root()]
+        );
+        assert_eq!(
+            tree.descendants(tree.root())
+                .map(|id| tree[id])
+                .collect::<Vec<_>>(),
+            vec!["root", "a", "b", "c"]
+        );
+        assert_eq!(tree.children(tree.root()), &[a, c]);
+    }
+
+    #[test]
+    fn ring() {
+        let mut ring: Ring<i32> = (1..=4).collect();
+        let ids: Vec<_> = ring.ids().collect();
+
+        ring.unlink(ids[0]);
+        ring.insert_after(ids[2], ids[0]);
+        assert_eq!(
+            ring.iter_from(ids[1])
+                .map(|(_, value)| *value)
+                .collect::<Vec<_>>(),
+            vec![2, 3, 1, 4]
+        );
+        assert_eq!(ring.previous(ids[1]), ids[3]);
+    }
+}
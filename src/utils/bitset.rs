@@ -0,0 +1,341 @@
+use std::{
+    hash::{Hash, Hasher},
+    ops::{BitAnd, BitOr},
+};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+fn word_and_bit(index: usize) -> (usize, u64) {
+    return (index / WORD_BITS, 1 << (index % WORD_BITS));
+}
+
+// trailing zero words don't change the contents of a set, so they are ignored for equality and hashing
+fn trimmed(words: &[u64]) -> &[u64] {
+    let len = words.len() - words.iter().rev().take_while(|word| **word == 0).count();
+    return &words[..len];
+}
+
+/// A set of small indexes that can hold up to `WORDS * 64` values without allocating.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct FixedBitSet<const WORDS: usize> {
+    words: [u64; WORDS],
+}
+
+/// A set of small indexes that grows to fit the largest index inserted.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BitSet {
+    words: Vec<u64>,
+}
+
+/// Iterates the indexes in a set in ascending order.
+pub(crate) struct Iter<'a> {
+    words: &'a [u64],
+    word_index: usize,
+    current: u64,
+}
+
+/// Iterates every subset of a set, starting with the empty set and ending with the set itself.
+pub(crate) struct Subsets<T> {
+    elements: Vec<usize>,
+    next: u64,
+    end: u64,
+    set: std::marker::PhantomData<T>,
+}
+
+impl<'a> Iter<'a> {
+    fn new(words: &'a [u64]) -> Self {
+        return Self {
+            words,
+            word_index: 0,
+            current: words.first().copied().unwrap_or(0),
+        };
+    }
+}
+
+impl Iterator for Iter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            self.word_index += 1;
+            self.current = *self.words.get(self.word_index)?;
+        }
+
+        let bit = self.current.trailing_zeros() as usize;
+        // clear the lowest set bit
+        self.current &= self.current - 1;
+        return Some(self.word_index * WORD_BITS + bit);
+    }
+}
+
+impl<T> Subsets<T> {
+    fn new(elements: Vec<usize>) -> Self {
+        assert!(
+            elements.len() < WORD_BITS,
+            "Too many elements to enumerate subsets of: {}",
+            elements.len()
+        );
+        let end = 1 << elements.len();
+        return Self {
+            elements,
+            next: 0,
+            end,
+            set: std::marker::PhantomData,
+        };
+    }
+}
+
+impl<T: FromIterator<usize>> Iterator for Subsets<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.next == self.end {
+            return None;
+        }
+
+        // each bit of the counter selects whether the matching element is in the subset
+        let selector = self.next;
+        self.next += 1;
+        return Some(
+            self.elements
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| selector & (1 << i) != 0)
+                .map(|(_, element)| *element)
+                .collect(),
+        );
+    }
+}
+
+impl<const WORDS: usize> FixedBitSet<WORDS> {
+    pub(crate) const CAPACITY: usize = WORDS * WORD_BITS;
+
+    pub(crate) fn new() -> Self {
+        return Self { words: [0; WORDS] };
+    }
+
+    /// Adds `index` to the set, returning whether it was newly added.
+    pub(crate) fn insert(&mut self, index: usize) -> bool {
+        assert!(
+            index < Self::CAPACITY,
+            "Index {} is out of range for a set of capacity {}",
+            index,
+            Self::CAPACITY
+        );
+        let (word, bit) = word_and_bit(index);
+        let added = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        return added;
+    }
+
+    pub(crate) fn contains(&self, index: usize) -> bool {
+        if index >= Self::CAPACITY {
+            return false;
+        }
+        let (word, bit) = word_and_bit(index);
+        return self.words[word] & bit != 0;
+    }
+
+    // popcount, kept for callers that count members rather than list them
+    #[allow(dead_code)]
+    pub(crate) fn len(&self) -> usize {
+        return self
+            .words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum();
+    }
+
+    pub(crate) fn iter(&self) -> Iter<'_> {
+        return Iter::new(&self.words);
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn subsets(&self) -> Subsets<Self> {
+        return Subsets::new(self.iter().collect());
+    }
+}
+
+impl<const WORDS: usize> Default for FixedBitSet<WORDS> {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl<const WORDS: usize> FromIterator<usize> for FixedBitSet<WORDS> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        return set;
+    }
+}
+
+impl<const WORDS: usize> Extend<usize> for FixedBitSet<WORDS> {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for index in iter {
+            self.insert(index);
+        }
+    }
+}
+
+impl<const WORDS: usize> BitOr for FixedBitSet<WORDS> {
+    type Output = Self;
+
+    fn bitor(mut self, rhs: Self) -> Self {
+        for (mine, theirs) in self.words.iter_mut().zip(rhs.words.iter()) {
+            *mine |= theirs;
+        }
+        return self;
+    }
+}
+
+impl<const WORDS: usize> BitAnd for FixedBitSet<WORDS> {
+    type Output = Self;
+
+    fn bitand(mut self, rhs: Self) -> Self {
+        for (mine, theirs) in self.words.iter_mut().zip(rhs.words.iter()) {
+            *mine &= theirs;
+        }
+        return self;
+    }
+}
+
+impl BitSet {
+    pub(crate) fn new() -> Self {
+        return Self { words: vec![] };
+    }
+
+    /// Adds `index` to the set, returning whether it was newly added.
+    pub(crate) fn insert(&mut self, index: usize) -> bool {
+        let (word, bit) = word_and_bit(index);
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        let added = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        return added;
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn contains(&self, index: usize) -> bool {
+        let (word, bit) = word_and_bit(index);
+        return self.words.get(word).is_some_and(|value| value & bit != 0);
+    }
+
+    // popcount, kept for callers that count members rather than list them
+    #[allow(dead_code)]
+    pub(crate) fn len(&self) -> usize {
+        return self
+            .words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum();
+    }
+
+    pub(crate) fn iter(&self) -> Iter<'_> {
+        return Iter::new(&self.words);
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn subsets(&self) -> Subsets<Self> {
+        return Subsets::new(self.iter().collect());
+    }
+
+    pub(crate) fn union_with(&mut self, other: &Self) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (mine, theirs) in self.words.iter_mut().zip(other.words.iter()) {
+            *mine |= theirs;
+        }
+    }
+
+    pub(crate) fn intersect_with(&mut self, other: &Self) {
+        self.words.truncate(other.words.len());
+        for (mine, theirs) in self.words.iter_mut().zip(other.words.iter()) {
+            *mine &= theirs;
+        }
+    }
+}
+
+impl PartialEq for BitSet {
+    fn eq(&self, other: &Self) -> bool {
+        return trimmed(&self.words) == trimmed(&other.words);
+    }
+}
+
+impl Eq for BitSet {}
+
+impl Hash for BitSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        trimmed(&self.words).hash(state);
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        return set;
+    }
+}
+
+impl Extend<usize> for BitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for index in iter {
+            self.insert(index);
+        }
+    }
+}
+
+impl BitOr for &BitSet {
+    type Output = BitSet;
+
+    fn bitor(self, rhs: Self) -> BitSet {
+        let mut set = self.clone();
+        set.union_with(rhs);
+        return set;
+    }
+}
+
+impl BitAnd for &BitSet {
+    type Output = BitSet;
+
+    fn bitand(self, rhs: Self) -> BitSet {
+        let mut set = self.clone();
+        set.intersect_with(rhs);
+        return set;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn growable() {
+        let mut set = BitSet::new();
+        assert!(set.insert(3));
+        assert!(set.insert(130));
+        assert!(!set.insert(3));
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![3, 130]);
+
+        assert!(set.contains(130));
+        assert!(!set.contains(4));
+        assert_eq!(&set & &BitSet::from_iter([3, 7]), BitSet::from_iter([3]));
+        assert_eq!(set.subsets().count(), 4);
+    }
+
+    #[test]
+    fn fixed() {
+        let set: FixedBitSet<2> = [0, 5, 64].into_iter().collect();
+        let other: FixedBitSet<2> = [5, 64, 100].into_iter().collect();
+        assert_eq!((set & other).iter().collect::<Vec<_>>(), vec![5, 64]);
+        assert_eq!((set | other).len(), 4);
+
+        let subsets: Vec<FixedBitSet<2>> = set.subsets().collect();
+        assert_eq!(subsets.len(), 8);
+        assert!(subsets.iter().all(|subset| *subset & set == *subset));
+    }
+}
@@ -1,34 +1,96 @@
-use std::{fmt::Debug, str::FromStr};
+use std::{
+    fmt::{Debug, Display},
+    fs::File,
+    io::{BufRead, BufReader},
+    str::FromStr,
+};
 
 use lazy_static::lazy_static;
 
+pub(crate) mod arena;
+pub(crate) mod bitset;
+pub(crate) mod ocr;
+pub(crate) mod parse;
+
 lazy_static! {
     pub(crate) static ref IS_TEST: bool =
         std::env::var("IS_TEST").unwrap_or("false".to_string()) == "true";
 }
 
-pub(crate) fn read_day(day: u8) -> String {
-    let str = std::fs::read_to_string(format!(
+fn day_path(day: u8) -> String {
+    return format!(
         "assets/{}{:02}.txt",
         if *IS_TEST { "test/" } else { "" },
         day
-    ));
+    );
+}
+
+pub(crate) fn read_day(day: u8) -> String {
+    let str = std::fs::read_to_string(day_path(day));
     return str.unwrap();
 }
 
+pub(crate) fn open_day(day: u8) -> BufReader<File> {
+    return BufReader::new(File::open(day_path(day)).unwrap());
+}
+
+/// Reads one line at a time, so only the current line is ever held in memory.
+pub(crate) fn stream_lines(reader: impl BufRead) -> impl Iterator<Item = String> {
+    return reader.lines().map(|line| line.unwrap());
+}
+
+/// Reads blank-line separated groups of lines, holding only the current group in memory. Like
+/// splitting on blank lines, a trailing blank line ends with an empty group.
+pub(crate) fn stream_grouped_lines(reader: impl BufRead) -> impl Iterator<Item = Vec<String>> {
+    let mut lines = stream_lines(reader);
+    let mut done = false;
+    return std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        let mut group = vec![];
+        for line in lines.by_ref() {
+            if line.is_empty() {
+                return Some(group);
+            }
+            group.push(line);
+        }
+        done = true;
+        return Some(group);
+    });
+}
+
+pub(crate) fn stream_parsed_lines<T: FromStr>(reader: impl BufRead) -> impl Iterator<Item = T>
+where
+    <T as FromStr>::Err: Debug,
+{
+    return stream_lines(reader).map(|line| line.parse().unwrap());
+}
+
+pub(crate) fn stream_day_lines(day: u8) -> impl Iterator<Item = String> {
+    return stream_lines(open_day(day));
+}
+
+pub(crate) fn stream_day_grouped_lines(day: u8) -> impl Iterator<Item = Vec<String>> {
+    return stream_grouped_lines(open_day(day));
+}
+
+pub(crate) fn stream_day_parsed_lines<T: FromStr>(day: u8) -> impl Iterator<Item = T>
+where
+    <T as FromStr>::Err: Debug,
+{
+    return stream_parsed_lines(open_day(day));
+}
+
 pub(crate) fn read_day_lines(day: u8) -> Vec<String> {
-    let str = read_day(day);
-    return str.lines().map(String::from).collect::<Vec<_>>();
+    return stream_day_lines(day).collect();
 }
 
 pub(crate) fn read_day_parsed_lines<T: FromStr>(day: u8) -> Vec<T>
 where
     <T as FromStr>::Err: Debug,
 {
-    return read_day_lines(day)
-        .iter()
-        .map(|val| val.parse().unwrap())
-        .collect();
+    return stream_day_parsed_lines(day).collect();
 }
 
 pub(crate) fn read_day_csv_lines(day: u8) -> Vec<Vec<String>> {
@@ -48,22 +110,87 @@ pub(crate) fn read_day_digit_lines(day: u8) -> Vec<Vec<u8>> {
 }
 
 pub(crate) fn read_day_grouped_lines(day: u8) -> Vec<Vec<String>> {
-    let lines = read_day_lines(day);
-    return lines
-        .split(|line| line == "")
-        .map(Vec::from)
-        .collect::<Vec<_>>();
+    return stream_day_grouped_lines(day).collect();
+}
+
+/// Reads blank-line separated groups of integers, holding only the current group in memory.
+pub(crate) fn stream_day_grouped_ints<T: FromStr>(day: u8) -> impl Iterator<Item = Vec<T>>
+where
+    <T as FromStr>::Err: Debug,
+{
+    return stream_day_grouped_lines(day).map(|group| {
+        return group
+            .into_iter()
+            .map(|str| str.parse::<T>().unwrap())
+            .collect();
+    });
 }
 
-pub(crate) fn read_day_grouped_ints(day: u8) -> Vec<Vec<i32>> {
-    let groups = read_day_grouped_lines(day);
+pub(crate) trait CheckedAdd: Copy + Default {
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked_add {
+    ($($int:ty),*) => {
+        $(impl CheckedAdd for $int {
+            fn checked_add(self, rhs: Self) -> Option<Self> {
+                return <$int>::checked_add(self, rhs);
+            }
+        })*
+    };
+}
+
+impl_checked_add!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct GroupOverflow {
+    /// The 1-indexed group whose total didn't fit.
+    pub(crate) group: usize,
+}
+
+impl Display for GroupOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "Total of group {} overflowed", self.group);
+    }
+}
+
+/// Totals each group, failing on the first group whose total overflows `T`. Groups are read one
+/// at a time, so they can come straight from a stream.
+pub(crate) fn checked_group_sums<T: CheckedAdd, G: IntoIterator<Item = T>>(
+    groups: impl IntoIterator<Item = G>,
+) -> Result<Vec<T>, GroupOverflow> {
     return groups
         .into_iter()
-        .map(|group| {
-            group
+        .enumerate()
+        .map(|(group, vals)| {
+            return vals
                 .into_iter()
-                .map(|str| str.parse::<i32>().unwrap())
-                .collect()
+                .try_fold(T::default(), |acc, val| acc.checked_add(val))
+                .ok_or(GroupOverflow { group: group + 1 });
         })
         .collect();
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn streamed_lines() {
+        let lines: Vec<String> = stream_lines("a\nb\n".as_bytes()).collect();
+        assert_eq!(lines, vec!["a", "b"]);
+
+        let nums: Vec<i32> = stream_parsed_lines("1\n-2".as_bytes()).collect();
+        assert_eq!(nums, vec![1, -2]);
+    }
+
+    #[test]
+    fn streamed_groups() {
+        let groups = |str: &str| stream_grouped_lines(str.as_bytes()).collect::<Vec<_>>();
+        assert_eq!(groups("a\nb\n\nc\n"), vec![vec!["a", "b"], vec!["c"]]);
+        assert_eq!(groups("a\n\n\nb"), vec![vec!["a"], vec![], vec!["b"]]);
+        // the same groups as splitting the lines on blank ones
+        assert_eq!(groups("a\n\n"), vec![vec!["a"], vec![]]);
+        assert_eq!(groups(""), vec![Vec::<String>::new()]);
+    }
+}
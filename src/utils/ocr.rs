@@ -0,0 +1,95 @@
+// each letter of the block font sits in a 5 wide cell; most glyphs only use the first 4 columns
+const CELL_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 6;
+
+const GLYPHS: [(char, [&str; GLYPH_HEIGHT]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+fn glyph_matches(glyph: &[&str; GLYPH_HEIGHT], pixels: &[Vec<bool>], x_offset: usize) -> bool {
+    return glyph.iter().zip(pixels.iter()).all(|(glyph_row, row)| {
+        return (0..CELL_WIDTH).all(|x| {
+            let expected = glyph_row.as_bytes().get(x) == Some(&b'#');
+            let lit = *row.get(x_offset + x).unwrap_or(&false);
+            return expected == lit;
+        });
+    });
+}
+
+/// Reads the letters drawn in a 6 pixel high grid of lit pixels, such as the Day 10 CRT.
+pub(crate) fn recognize(pixels: &[Vec<bool>]) -> Result<String, String> {
+    if pixels.len() != GLYPH_HEIGHT {
+        return Err(format!(
+            "Expected {} rows of pixels, found {}",
+            GLYPH_HEIGHT,
+            pixels.len()
+        ));
+    }
+
+    let width = pixels.iter().map(|row| row.len()).max().unwrap_or(0);
+    return (0..width)
+        .step_by(CELL_WIDTH)
+        .enumerate()
+        .map(|(i, x_offset)| {
+            return GLYPHS
+                .iter()
+                .find(|(_, glyph)| glyph_matches(glyph, pixels, x_offset))
+                .map(|(letter, _)| *letter)
+                .ok_or(format!("Unrecognized letter at position {}", i));
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // reads the letters from a picture where `lit` marks the lit pixels, one row per line
+    fn recognize_str(picture: &str, lit: char) -> Result<String, String> {
+        let pixels: Vec<Vec<bool>> = picture
+            .lines()
+            .map(|line| line.chars().map(|char| char == lit).collect())
+            .collect();
+        return recognize(&pixels);
+    }
+
+    #[test]
+    fn recognize_letters() {
+        let picture = [
+            "#..#.####.###..#.....##..",
+            "#..#.#....#..#.#....#..#.",
+            "####.###..#..#.#....#..#.",
+            "#..#.#....###..#....#..#.",
+            "#..#.#....#.#..#....#..#.",
+            "#..#.####.#..#.####..##..",
+        ]
+        .join("\n");
+        assert_eq!(recognize_str(&picture, '#'), Ok("HERLO".to_string()));
+    }
+
+    #[test]
+    fn unrecognized_letter() {
+        let picture = ["#", "#", "#", "#", "#", "#"].join("\n");
+        assert_eq!(
+            recognize_str(&picture, '#'),
+            Err("Unrecognized letter at position 0".to_string())
+        );
+    }
+}
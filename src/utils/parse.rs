@@ -0,0 +1,316 @@
+//! A small parser-combinator library for puzzle inputs.
+//!
+//! A parser is anything that takes the remaining input and either returns a value along with the
+//! input left after it, or a [`Failure`] pointing at where it went wrong. Plain functions with the
+//! signature `fn(&str) -> PResult<'_, T>` are parsers too, which is how recursive grammars are
+//! written: the function simply calls itself through the combinators it builds.
+//!
+//! Like Parsec, a parser that fails after consuming input is treated as an error rather than a
+//! reason to try something else, so malformed input is reported where it is malformed.
+
+use std::{fmt::Display, str::FromStr};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Failure<'a> {
+    rest: &'a str,
+    expected: &'static str,
+}
+
+pub(crate) type PResult<'a, T> = Result<(T, &'a str), Failure<'a>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ParseError {
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    pub(crate) expected: &'static str,
+    pub(crate) found: String,
+}
+
+impl<'a> Failure<'a> {
+    pub(crate) fn new(rest: &'a str, expected: &'static str) -> Self {
+        return Self { rest, expected };
+    }
+
+    fn consumed_from(&self, input: &str) -> bool {
+        return self.rest.len() != input.len();
+    }
+}
+
+impl ParseError {
+    fn from_failure(input: &str, failure: &Failure) -> Self {
+        let consumed = &input[..input.len() - failure.rest.len()];
+        let line = consumed.matches('\n').count() + 1;
+        let column = consumed
+            .rsplit('\n')
+            .next()
+            .map_or(0, |line| line.chars().count())
+            + 1;
+        let found = match failure.rest.lines().next() {
+            Some(rest) if !rest.is_empty() => format!("{:?}", rest),
+            Some(_) => "end of line".to_string(),
+            None => "end of input".to_string(),
+        };
+        return Self {
+            line,
+            column,
+            expected: failure.expected,
+            found,
+        };
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(
+            f,
+            "line {}, column {}: expected {}, found {}",
+            self.line, self.column, self.expected, self.found
+        );
+    }
+}
+
+pub(crate) trait Parser<T> {
+    fn parse<'a>(&self, input: &'a str) -> PResult<'a, T>;
+
+    fn map<U>(self, f: impl Fn(T) -> U) -> impl Parser<U>
+    where
+        Self: Sized,
+    {
+        return from_fn(move |input| {
+            let (value, rest) = self.parse(input)?;
+            return Ok((f(value), rest));
+        });
+    }
+
+    /// Runs `next` after this parser, keeping both values.
+    fn then<U>(self, next: impl Parser<U>) -> impl Parser<(T, U)>
+    where
+        Self: Sized,
+    {
+        return from_fn(move |input| {
+            let (first, rest) = self.parse(input)?;
+            let (second, rest) = next.parse(rest)?;
+            return Ok(((first, second), rest));
+        });
+    }
+
+    /// Runs `next` after this parser, keeping only this parser's value.
+    fn skip<U>(self, next: impl Parser<U>) -> impl Parser<T>
+    where
+        Self: Sized,
+    {
+        return from_fn(move |input| {
+            let (value, rest) = self.parse(input)?;
+            let (_, rest) = next.parse(rest)?;
+            return Ok((value, rest));
+        });
+    }
+
+    /// Tries `other` if this parser fails without consuming any input.
+    fn or(self, other: impl Parser<T>) -> impl Parser<T>
+    where
+        Self: Sized,
+    {
+        return from_fn(move |input| match self.parse(input) {
+            Err(failure) if !failure.consumed_from(input) => return other.parse(input),
+            result => return result,
+        });
+    }
+
+    /// Replaces what this parser reports as expected when it fails without consuming input.
+    fn expect(self, expected: &'static str) -> impl Parser<T>
+    where
+        Self: Sized,
+    {
+        return from_fn(move |input| match self.parse(input) {
+            Err(failure) if !failure.consumed_from(input) => {
+                return Err(Failure::new(input, expected))
+            }
+            result => return result,
+        });
+    }
+}
+
+impl<T, F> Parser<T> for F
+where
+    F: for<'a> Fn(&'a str) -> PResult<'a, T>,
+{
+    fn parse<'a>(&self, input: &'a str) -> PResult<'a, T> {
+        return self(input);
+    }
+}
+
+/// Pins a closure to the parser signature, so its lifetimes are inferred correctly.
+pub(crate) fn from_fn<T, F>(f: F) -> F
+where
+    F: for<'a> Fn(&'a str) -> PResult<'a, T>,
+{
+    return f;
+}
+
+/// Parses the whole of `input`, ignoring trailing whitespace.
+pub(crate) fn parse_complete<T>(parser: impl Parser<T>, input: &str) -> Result<T, ParseError> {
+    let trimmed = input.trim_end();
+    let result = parser.parse(trimmed).and_then(|(value, rest)| {
+        if !rest.is_empty() {
+            return Err(Failure::new(rest, "end of input"));
+        }
+        return Ok(value);
+    });
+    return result.map_err(|failure| ParseError::from_failure(trimmed, &failure));
+}
+
+pub(crate) fn literal(expected: &'static str) -> impl Parser<()> {
+    return from_fn(move |input| match input.strip_prefix(expected) {
+        Some(rest) => return Ok(((), rest)),
+        None => return Err(Failure::new(input, expected)),
+    });
+}
+
+/// Consumes the longest prefix whose characters all match `predicate`, which may be empty.
+pub(crate) fn take_while(predicate: impl Fn(char) -> bool) -> impl Parser<String> {
+    return from_fn(move |input| {
+        let end = input
+            .find(|char: char| !predicate(char))
+            .unwrap_or(input.len());
+        return Ok((input[..end].to_string(), &input[end..]));
+    });
+}
+
+/// Consumes any run of whitespace, including newlines.
+pub(crate) fn whitespace() -> impl Parser<()> {
+    return take_while(char::is_whitespace).map(|_| ());
+}
+
+/// Parses an optionally negative decimal integer into any integer type.
+pub(crate) fn int<T: FromStr>() -> impl Parser<T> {
+    return from_fn(|input| {
+        let digits_start = if input.starts_with('-') { 1 } else { 0 };
+        let end = input[digits_start..]
+            .find(|char: char| !char.is_ascii_digit())
+            .map_or(input.len(), |end| end + digits_start);
+        if end == digits_start {
+            return Err(Failure::new(&input[digits_start..], "integer"));
+        }
+        match input[..end].parse() {
+            Ok(value) => return Ok((value, &input[end..])),
+            Err(_) => return Err(Failure::new(input, "integer in range")),
+        }
+    });
+}
+
+pub(crate) fn preceded<T, U>(prefix: impl Parser<U>, parser: impl Parser<T>) -> impl Parser<T> {
+    return from_fn(move |input| {
+        let (_, rest) = prefix.parse(input)?;
+        return parser.parse(rest);
+    });
+}
+
+pub(crate) fn delimited<T, U, V>(
+    open: impl Parser<U>,
+    parser: impl Parser<T>,
+    close: impl Parser<V>,
+) -> impl Parser<T> {
+    return preceded(open, parser.skip(close));
+}
+
+/// Parses `parser` zero or more times.
+pub(crate) fn many<T>(parser: impl Parser<T>) -> impl Parser<Vec<T>> {
+    return from_fn(move |mut input| {
+        let mut values = vec![];
+        loop {
+            match parser.parse(input) {
+                // stop on parsers that succeed without consuming anything, they would loop forever
+                Ok((_, rest)) if rest.len() == input.len() => return Ok((values, input)),
+                Ok((value, rest)) => {
+                    values.push(value);
+                    input = rest;
+                }
+                Err(failure) if !failure.consumed_from(input) => return Ok((values, input)),
+                Err(failure) => return Err(failure),
+            }
+        }
+    });
+}
+
+/// Parses zero or more `item`s separated by `separator`, without a trailing separator.
+pub(crate) fn separated<T, U>(
+    item: impl Parser<T>,
+    separator: impl Parser<U>,
+) -> impl Parser<Vec<T>> {
+    return from_fn(move |input| {
+        let (first, mut input) = match item.parse(input) {
+            Ok(result) => result,
+            Err(failure) if !failure.consumed_from(input) => return Ok((vec![], input)),
+            Err(failure) => return Err(failure),
+        };
+
+        let mut values = vec![first];
+        loop {
+            let rest = match separator.parse(input) {
+                Ok((_, rest)) => rest,
+                Err(failure) if !failure.consumed_from(input) => return Ok((values, input)),
+                Err(failure) => return Err(failure),
+            };
+            let (value, rest) = item.parse(rest)?;
+            values.push(value);
+            input = rest;
+        }
+    });
+}
+
+/// Parses one `item` per line.
+#[allow(dead_code)]
+pub(crate) fn lines<T>(item: impl Parser<T>) -> impl Parser<Vec<T>> {
+    return separated(item, literal("\n"));
+}
+
+/// Parses `item`s separated by blank lines.
+pub(crate) fn blocks<T>(item: impl Parser<T>) -> impl Parser<Vec<T>> {
+    return separated(item, literal("\n\n"));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Tree {
+        Leaf(i32),
+        Node(Vec<Tree>),
+    }
+
+    fn tree(input: &str) -> PResult<'_, Tree> {
+        return int()
+            .map(Tree::Leaf)
+            .or(
+                delimited(literal("["), separated(tree, literal(",")), literal("]"))
+                    .map(Tree::Node),
+            )
+            .expect("tree")
+            .parse(input);
+    }
+
+    #[test]
+    fn recursive_lines() {
+        let parsed = parse_complete(lines(tree), "[1,[-2]]\n[]\n3\n").unwrap();
+        assert_eq!(
+            parsed,
+            vec![
+                Tree::Node(vec![Tree::Leaf(1), Tree::Node(vec![Tree::Leaf(-2)])]),
+                Tree::Node(vec![]),
+                Tree::Leaf(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn error_position() {
+        let error = parse_complete(lines(tree), "[1,2]\n[3,x]").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 4));
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 4: expected tree, found \"x]\""
+        );
+    }
+}